@@ -5,6 +5,7 @@ use rand::{distributions::Uniform, Rng};
 use serde::{Deserialize as Deserialise, Serialize as Serialise};
 use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::env;
 use std::fmt::{self, Debug, Display};
 use std::fs;
 use std::io::{stdin, Read, Write};
@@ -28,7 +29,38 @@ use colors_transform::{Color, Rgb};
 
 mod modules;
 
-use modules::{consts::*, emotes};
+use modules::{
+  ads::AdBreaks,
+  alerts::{Alert, AlertKind, AlertQueue},
+  bot_guard::{self, BotGuard},
+  bot_users::BotPolicy,
+  cli::CliCommand,
+  clock,
+  config::Config,
+  consts::*,
+  effects::Effects,
+  emotes,
+  filters::ChatFilters,
+  flood::FloodGuard,
+  followers::{self, FollowWelcomes},
+  helix::{self, ChannelInformation},
+  hype_train::HypeTrainTracker,
+  moderation::{FlaggedMessage, ModerationAction, SpamCheck, Strikes},
+  modlog::{self, ModLogEntry},
+  overlay::{self, OverlayEvent, OverlayServer},
+  permits::Permits,
+  polls::PollTracker,
+  raids::{self, Raiders, ShoutoutQueue},
+  redemptions::{PointsRedemption, RedeemOutcome},
+  review::{ReviewDecision, ReviewQueue},
+  rewards::{self, RewardAction, RewardRegistry, RewardToggle},
+  terminal::{TerminalCommand, TerminalInput},
+  thanks::ThanksKind,
+  tts::{self, TtsVoices},
+  tts_filter::{self, EmoteNames, TtsRejection},
+  tts_queue::{TtsControl, TtsPriority, TtsQueue, TtsRequest},
+  viewers::Viewers,
+};
 //use modules::TwitchChat;
 
 use twitcheventsub::{
@@ -127,6 +159,21 @@ enum ChatCommands {
   Holee,
   Spill,
   Heckies,
+  Pardon,
+  ModLog,
+  Approve,
+  Nuke,
+  Block,
+  Unblock,
+  AllowLink,
+  Permit,
+  Voice,
+  Tts,
+  Rewards,
+  Effects,
+  Poll,
+  Predict,
+  FollowAge,
 }
 
 impl ChatCommands {
@@ -229,25 +276,6 @@ macro_rules! gp {
   };
 }
 
-fn run_tts(text: String) {
-  let mut file = fs::File::create(SPEECH_FILE).unwrap();
-  file.write_all(format!("{}\n", text).as_bytes()).unwrap();
-  file.flush().unwrap();
-
-  thread::spawn(|| {
-    if let Ok(_) = Command::new("dsnote").arg("./speech").output() {
-      thread::sleep(Duration::from_millis(1000));
-      if let Err(e) = Command::new("dsnote")
-        .arg("--action")
-        .arg("start-reading")
-        .output()
-      {
-        error!("TTS failed to read: {}", e);
-      }
-    }
-  });
-}
-
 pub fn print_fragments(
   twitch: &mut TwitchEventSubApi,
   emote_buffer: &mut HashMap<String, u32>,
@@ -338,19 +366,25 @@ pub fn recreate_chat<T: Into<String>>(
   })
 }
 
-fn shoutout<T: Into<String>>(twitch: &mut TwitchEventSubApi, username: T) {
-  let username = username.into();
-  let _ = twitch.send_chat_message(format!(
-    "{} is an awesome streamer, follow them at http://twitch.tv/{}",
-    username, username,
-  ));
-  let _ = twitch.send_announcement(
-    format!(
-      "{} is an awesome streamer, follow them at http://twitch.tv/{}",
-      username, username,
+// Mentions what they were last streaming when their channel is known
+fn shoutout(
+  twitch: &mut TwitchEventSubApi,
+  username: &str,
+  login: &str,
+  channel: Option<&ChannelInformation>,
+) {
+  let shoutout_text = match channel {
+    Some(channel) if !channel.game_name.is_empty() => format!(
+      "{} is an awesome streamer, they were last playing {}: \"{}\". Follow them at https://twitch.tv/{}",
+      username, channel.game_name, channel.title, login,
     ),
-    None::<String>,
-  );
+    _ => format!(
+      "{} is an awesome streamer, follow them at https://twitch.tv/{}",
+      username, login,
+    ),
+  };
+  let _ = twitch.send_chat_message(shoutout_text.to_owned());
+  let _ = twitch.send_announcement(shoutout_text, None::<String>);
 }
 
 #[derive(Serialise, Deserialise)]
@@ -358,6 +392,9 @@ pub struct Counters {
   heckies: u128,
   holee: u128,
   spilled: u128,
+  // Counters incremented by channel point rewards
+  #[serde(default)]
+  custom: HashMap<String, u128>,
 }
 
 impl Default for Counters {
@@ -366,6 +403,7 @@ impl Default for Counters {
       heckies: 0,
       holee: 0,
       spilled: 0,
+      custom: HashMap::new(),
     }
   }
 }
@@ -388,6 +426,12 @@ impl Counters {
     file.flush().unwrap();
   }
 
+  pub fn increment(&mut self, name: &str) -> u128 {
+    let count = self.custom.entry(name.to_owned()).or_insert(0);
+    *count += 1;
+    *count
+  }
+
   pub fn holy_text(&self) -> String {
     format!("has said holy {} times!", self.holee)
   }
@@ -401,14 +445,11 @@ impl Counters {
   }
 }
 
-fn main() {
-  let _ = simple_logging::log_to_file("twitch_events.log", LevelFilter::Info);
-
+fn connect_to_twitch() -> TwitchEventSubApi {
   let keys = TwitchKeys::from_secrets_env().unwrap();
   let redirect_url = "http://localhost:3000";
 
-  println!("Owlbot booting up!");
-  let twitch = TwitchEventSubApi::builder(keys.clone())
+  TwitchEventSubApi::builder(keys.clone())
     .set_redirect_url(redirect_url)
     .generate_new_token_if_insufficent_scope(true)
     .generate_new_token_if_none(true)
@@ -430,19 +471,26 @@ fn main() {
       Subscription::ChannelPointsCustomRewardRedeem,
       Subscription::ChannelPointsAutoRewardRedeem,
       Subscription::PermissionReadModerator,
-      //Subscription::ChannelPollBegin,
-      //Subscription::ChannelPollProgress,
-      //Subscription::ChannelPollEnd,
+      Subscription::ChannelPollBegin,
+      Subscription::ChannelPollProgress,
+      Subscription::ChannelPollEnd,
+      Subscription::ChannelPredictionBegin,
+      Subscription::ChannelPredictionProgress,
+      Subscription::ChannelPredictionLock,
+      Subscription::ChannelPredictionEnd,
       //Subscription::ChannelGoalBegin,
       //Subscription::ChannelGoalProgress,
       //Subscription::ChannelGoalEnd,
       Subscription::ChannelHypeTrainBegin,
       Subscription::ChannelHypeTrainProgress,
       Subscription::ChannelHypeTrainEnd,
-      //Subscription::ChannelShoutoutCreate,
+      // Only needed for the permission to send shoutouts
+      Subscription::ChannelShoutoutCreate,
+      raids::raid_permission(),
       //Subscription::ChannelShoutoutReceive,
       Subscription::ChatMessage,
       //Subscription::BanTimeoutUser,
+      Subscription::PermissionBanTimeoutUser,
       Subscription::PermissionDeleteMessage,
       Subscription::PermissionReadChatters,
       Subscription::PermissionSendAnnouncements,
@@ -451,6 +499,7 @@ fn main() {
       Subscription::PermissionIRCRead,
       Subscription::PermissionIRCWrite,
     ])
+    .add_subscriptions(bot_guard::protection_permissions())
     //.add_subscription(Subscription::ChatMessage)
     //.add_subscription(Subscription::ChannelPointsCustomRewardRedeem)
     //.add_subscription(Subscription::BanTimeoutUser)
@@ -470,9 +519,24 @@ fn main() {
     //     transport: Transport::new(""),
     //   })
     //))
-    .build();
+    .build()
+    .unwrap()
+}
+
+fn main() {
+  let _ = simple_logging::log_to_file("twitch_events.log", LevelFilter::Info);
+
+  let args = env::args().skip(1).collect::<Vec<_>>();
+  if !args.is_empty() {
+    match CliCommand::parse(&args) {
+      Some(command) => command.run(),
+      None => println!("{}", CliCommand::usage()),
+    }
+    return;
+  }
 
-  let mut twitch = twitch.unwrap();
+  println!("Owlbot booting up!");
+  let mut twitch = connect_to_twitch();
   println!("Owlbot has been equipped!");
 
   let mut bots_recently_vanquished = 0;
@@ -509,6 +573,11 @@ fn main() {
   // holee 136
   let mut counter = Counters::load();
 
+  let config = Config::load();
+  let mut strikes = Strikes::load();
+  let mut chat_filters = ChatFilters::load();
+  let mut viewers = Viewers::load();
+
   if let Ok(chatters) = twitch.get_chatters() {
     for chatter in chatters.data {
       if !rank_buffer.contains_key(&chatter.name) {
@@ -517,17 +586,6 @@ fn main() {
     }
   }
 
-  // Happy -  English Britsh (Piper Semaine PrudenceMedium Female) - Happy TTS
-  // Nervous - Piper Jenny Medium Female
-  // NoFun VCTK p236 medium
-  // Clear - Piper Amy Low female
-  // Robot - RHVoice Slt Female
-  // Calm - Piper Kathleen Low Female
-  // Polite - Piper HFC Medium female
-  // Cute - VCTK p236 medium
-
-  // piper lessac high female - seems legit
-
   {
     let mut rng = thread_rng();
 
@@ -539,29 +597,39 @@ fn main() {
     let mut recent_loops: u32 = 0;
     let mut duration = 0;
 
-    let mut followers_in_last_10secs = 0;
-    let mut follower_timer = 0.0;
-    let delta_time = Instant::now();
+    let mut bot_guard = BotGuard::new(config.bot_guard.clone());
+    let mut flood_guard = FloodGuard::new(config.flood.clone());
 
     let mut past_chat_messages: Vec<ChatMessage> = Vec::new();
 
-    let mut tts_queue: Vec<String> = Vec::new();
-    let mut last_message_spoken = Instant::now();
-    let mut wait_duration = 5;
+    let mut review_queue = ReviewQueue::default();
+    let mut permits = Permits::default();
+    let mut effects = Effects::default();
+    let mut review_decisions: Vec<(ReviewDecision, u32)> = Vec::new();
+
+    let terminal_input = TerminalInput::spawn();
+    println!("{}", TerminalCommand::help());
+
+    let tts_voices = TtsVoices::new(&config.tts);
+    let mut tts_queue = TtsQueue::default();
+    let emote_names = EmoteNames::fetch(&mut twitch, &config.tts.filter);
 
-    // let reward_response = twitch.create_custom_reward(CreateCustomReward {
-    //   title: "TestCustomReward".to_string(),
-    //   cost: 500,
-    //   ..Default::default()
-    // });
+    let overlay = OverlayServer::start(&config.overlay);
+    let mut alerts = AlertQueue::new(config.alerts.clone());
 
-    // println!("{:?}", reward_response);
-    // if let Ok(rewards) = reward_response {
-    //   println!("{:?}", rewards);
-    //   if rewards.data.len() > 0 {
-    //     let _ = twitch.delete_custom_reward(rewards.data[0].id.to_owned());
-    //   }
-    // }
+    let mut raiders = Raiders::load();
+    let mut shoutouts = ShoutoutQueue::default();
+    let mut hype_trains = HypeTrainTracker::default();
+    let mut polls = PollTracker::default();
+    let mut ad_breaks = AdBreaks::new(config.ads.clone());
+    let mut follow_welcomes = FollowWelcomes::new(config.followers.clone());
+
+    let mut reward_registry = RewardRegistry::load();
+    if reward_registry.rewards.is_empty() {
+      reward_registry.import_existing(&mut twitch);
+    }
+    reward_registry.add_legacy_effects();
+    reward_registry.sync(&mut twitch);
 
     loop {
       if recent_loops > 100 {
@@ -570,27 +638,78 @@ fn main() {
         recent_loops += 1;
       }
 
-      follower_timer -= delta_time.elapsed().as_secs_f32();
-      if follower_timer <= 0.0 {
-        if followers_in_last_10secs > 10 {
-          follower_timer = 120.0;
-          let follow_bot_warning = "Warning: Channel may be current being follow botted!";
-          let _ = twitch.send_chat_message(follow_bot_warning);
-          println!("{}", follow_bot_warning);
-          warn!("{}", follow_bot_warning);
-        } else {
-          followers_in_last_10secs = 0;
-          follower_timer = 10.0;
+      if let Some(guard_event) = bot_guard.tick(Instant::now()) {
+        bot_guard.handle(&mut twitch, guard_event);
+      }
+
+      if let Some(flood_event) = flood_guard.tick(Instant::now()) {
+        flood_guard.handle(&mut twitch, &flood_event);
+      }
+
+      for command in terminal_input.commands() {
+        match command {
+          TerminalCommand::Review(decision, id) => review_decisions.push((decision, id)),
+          TerminalCommand::ShowReviewQueue => review_queue.print(),
+          TerminalCommand::ShowPermits => permits.print(Instant::now()),
+          TerminalCommand::ShowEffects => effects.print(Instant::now()),
+          TerminalCommand::ShowHypeTrain => hype_trains.print(),
+          TerminalCommand::Tts(control) => {
+            println!("{}", tts_queue.control(&mut twitch, &mut viewers, control))
+          }
+          TerminalCommand::Raid(command) => command.run(&mut twitch, &config.raids, &raiders),
+          TerminalCommand::Help => println!("{}", TerminalCommand::help()),
         }
       }
 
-      if tts_queue.len() > 0 && last_message_spoken.elapsed().as_secs() > wait_duration {
-        let text = tts_queue.remove(0);
-        wait_duration = (text.len() / 100 * 7).max(5).min(18) as u64;
-        run_tts(text);
-        last_message_spoken = Instant::now();
+      permits.expire(Instant::now());
+
+      for effect in effects.expire(Instant::now()) {
+        let expired_text = format!("{} has worn off!", effect.name);
+        let _ = twitch.send_chat_message(expired_text.to_owned());
+        println!("{} (from {})", expired_text, effect.redeemed_by);
+      }
+
+      for (decision, id) in review_decisions.drain(..) {
+        let Some(held) = review_queue.take(id) else {
+          println!("There is no held message #{}", id);
+          continue;
+        };
+
+        match decision {
+          ReviewDecision::Approve => {
+            println!("Approved #{} from {}", id, held.flagged.username);
+            held.chat_message.print(&mut twitch, &mut emote_buffer);
+            past_chat_messages.push(held.chat_message);
+            if past_chat_messages.len() > 20 {
+              past_chat_messages.remove(0);
+            }
+            rank_buffer.entry(held.flagged.username).or_insert(0);
+          }
+          ReviewDecision::Delete | ReviewDecision::Ban => {
+            let chosen_action = if decision == ReviewDecision::Ban {
+              Some(ModerationAction::Ban)
+            } else {
+              None
+            };
+            if strikes.punish(
+              &mut twitch,
+              &config.moderation,
+              &held.flagged,
+              chosen_action,
+            ) {
+              bots_recently_vanquished += 1;
+              time_since_last_vanquish = Instant::now();
+            }
+          }
+        }
       }
 
+      ad_breaks.tick(&mut twitch, &mut tts_queue, &mut alerts, Instant::now());
+      follow_welcomes.tick(&mut twitch, bot_guard.is_engaged(), Instant::now());
+      alerts.tick(&overlay, &mut tts_queue, Instant::now());
+      shoutouts.tick(&twitch, Instant::now());
+      tts_queue.tick(&mut twitch, &tts_voices);
+
       if bots_recently_vanquished > 0 {
         if time_since_last_vanquish.elapsed().as_secs_f32() > 30.0 {
           let bots_sent_to_owlkatraz = format!(
@@ -612,25 +731,115 @@ fn main() {
           ResponseType::Event(event) => {
             match event {
               Event::Raid(raid_data) => {
-                println!(
-                  "Raid from {} with {} viewers!",
-                  raid_data.from_broadcaster.name, raid_data.viewers
+                alerts.push(
+                  Alert::new(
+                    AlertKind::Raid,
+                    format!(
+                      "Raid from {} with {} viewers!",
+                      raid_data.from_broadcaster.name, raid_data.viewers
+                    ),
+                    OverlayEvent::Raid {
+                      user: raid_data.from_broadcaster.name.to_owned(),
+                      viewers: raid_data.viewers,
+                    },
+                  ),
+                  Instant::now(),
                 );
-                if raid_data.viewers >= 5 {
-                  shoutout(&mut twitch, raid_data.from_broadcaster.name);
-                  //let _ =
-                  //  twitch.send_chat_message(format!("!so {}", raid_data.from_broadcaster.name));
+                let raider_age =
+                  bot_guard::account_age_days(&mut twitch, &raid_data.from_broadcaster.login);
+                // Brand new accounts are how hate raids arrive, they get no grace
+                if raider_age.is_some_and(|age| age >= config.bot_guard.new_account_days) {
+                  flood_guard.raided(Instant::now());
+                }
+                if let Some(guard_event) =
+                  raider_age.and_then(|age| bot_guard.raid(age, Instant::now()))
+                {
+                  bot_guard.handle(&mut twitch, guard_event);
+                }
+
+                let raider = raiders.record(
+                  &raid_data.from_broadcaster.id,
+                  &raid_data.from_broadcaster.login,
+                  &raid_data.from_broadcaster.name,
+                  raid_data.viewers,
+                );
+                let channel =
+                  match helix::get_channel_information(&twitch, &raid_data.from_broadcaster.id) {
+                    Ok(channel) => channel,
+                    Err(e) => {
+                      error!("Failed to get the raider's channel: {:?}", e);
+                      None
+                    }
+                  };
+                let (game, title) = match &channel {
+                  Some(channel) if !channel.game_name.is_empty() => {
+                    (channel.game_name.as_str(), channel.title.as_str())
+                  }
+                  _ => ("something mysterious", ""),
+                };
+                config.thanks.thank_with(
+                  &mut twitch,
+                  if raider.raids > 1 {
+                    ThanksKind::ReturningRaid
+                  } else {
+                    ThanksKind::Raid
+                  },
+                  &raid_data.from_broadcaster.name,
+                  raid_data.viewers,
+                  "",
+                  &[
+                    ("game", game),
+                    ("title", title),
+                    ("raids", &raider.raids.to_string()),
+                  ],
+                );
+
+                if raid_data.viewers >= config.raids.shoutout_min_viewers {
+                  shoutout(
+                    &mut twitch,
+                    &raid_data.from_broadcaster.name,
+                    &raid_data.from_broadcaster.login,
+                    channel.as_ref(),
+                  );
+                  shoutouts.push(
+                    &raid_data.from_broadcaster.id,
+                    &raid_data.from_broadcaster.name,
+                  );
                 }
               }
               Event::Follow(follow_data) => {
-                followers_in_last_10secs += 1;
-                //println!("{} followed!", follow_data.);
+                alerts.push(
+                  Alert::new(
+                    AlertKind::Follow,
+                    format!("{} followed!", follow_data.user.name),
+                    OverlayEvent::Follow {
+                      user: follow_data.user.name.to_owned(),
+                    },
+                  ),
+                  Instant::now(),
+                );
+                if let Some(guard_event) = bot_guard.follow(Instant::now()) {
+                  bot_guard.handle(&mut twitch, guard_event);
+                }
+
+                // Follows during a burst are most likely the bots themselves
+                if !bot_guard.is_engaged() {
+                  viewers.get_mut(&follow_data.user.login).followed_at = Some(
+                    clock::parse_timestamp(&follow_data.followed_at)
+                      .unwrap_or_else(clock::unix_timestamp),
+                  );
+                  viewers.save();
+                  follow_welcomes.push(&follow_data.user.name, Instant::now());
+                }
               }
               Event::AdBreakBegin(break_data) => {
-                //twitch.send_chat_message(format!(
-                //  "A {}min Ad has attacked! I try my best to not do anything interesting.",
-                //  break_data.duration_seconds / 60
-                //));
+                ad_breaks.begin(
+                  &mut twitch,
+                  &mut tts_queue,
+                  &mut alerts,
+                  break_data.duration_seconds,
+                  Instant::now(),
+                );
               }
               Event::MessageDeleted(deleted_message) => {
                 //println!("Message was deleted ID: {}", deleted_message.message_id);
@@ -664,191 +873,442 @@ fn main() {
                 }
               }
               Event::PointsCustomRewardRedeem(reward) => {
-                let title = reward.reward.title;
-                let user = reward.user.name;
-                let input = reward.user_input;
-
-                if title.contains("water") {
-                  println!("{} watered the Owl!", user);
-                }
-                if title.contains("EU") {
-                  println!("{} has sent Owl to the EU!", user);
-                }
-                if title.contains("editor") {
-                  println!("{} has requested Owl uses {}", user, input);
-                }
-                if title.contains("Video") {
-                  println!("{}: Redeemed Video", user);
+                let user = reward.user.name.to_owned();
+                let login = reward.user.login.to_owned();
+                let input = reward.user_input.to_owned();
+                let Some((actions, managed)) = reward_registry
+                  .find(&reward.reward.id, &reward.reward.title)
+                  .map(|definition| (definition.actions.to_owned(), definition.managed))
+                else {
+                  println!(
+                    "{} redeemed {}, which isn't in {}",
+                    user, reward.reward.title, REWARDS_FILE
+                  );
                   continue;
-                }
-                if title.contains("game") {
-                  println!("{} has request Owl to live a rust free life.", user);
-                }
-
-                if title.contains("Feed the fimsh!") {
-                  println!("{} fed the fimsh!", user);
-                }
-                if title.contains("Feed the fimsh! x10") {
-                  println!("{} fed the fimsh 10x times!", user);
-                }
-
-                if title.contains("TTS") {
-                  if input.split_whitespace().count() > 1 {
-                    tts_queue.push(format!("{} says {}", user, input));
-                  } else {
-                    tts_queue.push(format!("{}", input));
+                };
+                let points_redemption =
+                  PointsRedemption::new(&config.redemptions, &reward, managed);
+                overlay.send(OverlayEvent::Redeem {
+                  user: user.to_owned(),
+                  reward: reward.reward.title.to_owned(),
+                  input: overlay::viewer_text(&input, &chat_filters),
+                });
+
+                let not_ranked = || {
+                  RedeemOutcome::Failed("you need to chat before your rank can change".to_owned())
+                };
+                let mut outcome = RedeemOutcome::Succeeded;
+                let mut points = 0;
+                let mut great_fimsh_points: i32 = 0;
+                let mut tts_request = None;
+                for action in actions {
+                  // A failed redeem is refunded, so nothing after it should happen
+                  if matches!(outcome, RedeemOutcome::Failed(_)) {
+                    break;
                   }
-                }
 
-                let mut great_fimsh_points: i32 = 0;
-                if let Some(viewer_num) = rank_buffer.get_mut(&user) {
-                  let mut points = 0;
-                  if title.contains("RankUp") {
-                    points = (rng.gen::<f32>() * 3.0).floor() as u32;
-                    *viewer_num += points;
-                    if points > 0 {
-                      let rank_up_text =
-                        format!("{}'s rank went up a little bit! (+{}P)", user, points);
-                      let _ = twitch.send_chat_message(rank_up_text.to_owned());
-                      println!("{}", rank_up_text);
-                    } else {
-                      great_fimsh_points = (rng.gen::<f32>() * 3.0).ceil() as i32;
-                      if great_fimsh_points == 3 {
-                        //*viewer_num -= 4;
-                        *viewer_num = viewer_num.saturating_sub(4);
-                        points = 1;
-                        great_fimsh_points += 1;
-                        let great_fimsh_text = format!("{}'s rank got stuck and then was help by the great fimsh, so it gave some of it's points to the great fimsh!", user);
-                        let _ = twitch.send_chat_message(great_fimsh_text.to_owned());
-                        println!("{}", great_fimsh_text);
+                  match &action {
+                    RewardAction::Print { text } => {
+                      println!("{}", rewards::fill_template(text, &user, &input));
+                    }
+                    RewardAction::AddPoints { points: added } => match rank_buffer.get_mut(&user) {
+                      Some(viewer_num) => {
+                        *viewer_num += added;
+                        points += added;
+                      }
+                      None => outcome = not_ranked(),
+                    },
+                    RewardAction::RankUp => {
+                      let Some(viewer_num) = rank_buffer.get_mut(&user) else {
+                        outcome = not_ranked();
+                        continue;
+                      };
+                      let gained = (rng.gen::<f32>() * 3.0).floor() as u32;
+                      *viewer_num += gained;
+                      points += gained;
+                      if gained > 0 {
+                        let rank_up_text =
+                          format!("{}'s rank went up a little bit! (+{}P)", user, gained);
+                        let _ = twitch.send_chat_message(rank_up_text.to_owned());
+                        println!("{}", rank_up_text);
+                        overlay.send(OverlayEvent::RankUp {
+                          user: user.to_owned(),
+                          points: gained,
+                        });
                       } else {
-                        let didnt_budge_text = format!(
-                          "{}'s rank didn't budge because the great fimsh stole it!",
-                          user
-                        );
-                        let _ = twitch.send_chat_message(didnt_budge_text.to_owned());
-                        println!("{}", didnt_budge_text);
+                        great_fimsh_points = (rng.gen::<f32>() * 3.0).ceil() as i32;
+                        if great_fimsh_points == 3 {
+                          //*viewer_num -= 4;
+                          *viewer_num = viewer_num.saturating_sub(4);
+                          points += 1;
+                          great_fimsh_points += 1;
+                          let great_fimsh_text = format!("{}'s rank got stuck and then was help by the great fimsh, so it gave some of it's points to the great fimsh!", user);
+                          let _ = twitch.send_chat_message(great_fimsh_text.to_owned());
+                          println!("{}", great_fimsh_text);
+                        } else {
+                          let didnt_budge_text = format!(
+                            "{}'s rank didn't budge because the great fimsh stole it!",
+                            user
+                          );
+                          let _ = twitch.send_chat_message(didnt_budge_text.to_owned());
+                          println!("{}", didnt_budge_text);
+                        }
                       }
                     }
-                  }
-                  if title.contains("RankDown") {
-                    great_fimsh_points =
-                      -((rng.gen::<f32>() * 4.0).floor() as i32).max(*viewer_num as i32);
+                    RewardAction::RankDown => {
+                      let Some(viewer_num) = rank_buffer.get(&user) else {
+                        outcome = not_ranked();
+                        continue;
+                      };
+                      great_fimsh_points =
+                        -((rng.gen::<f32>() * 4.0).floor() as i32).max(*viewer_num as i32);
 
-                    let _ = twitch.send_chat_message(format!(
-                      "The great fimsh's rank went down a little bit! (-{}P)",
-                      great_fimsh_points
-                    ));
+                      let _ = twitch.send_chat_message(format!(
+                        "The great fimsh's rank went down a little bit! (-{}P)",
+                        great_fimsh_points
+                      ));
+                    }
+                    RewardAction::Tts => {
+                      let (prefix_voice, input) = tts::split_voice_prefix(&input);
+                      let voice = prefix_voice
+                        .filter(|voice| tts_voices.contains(voice))
+                        .or_else(|| viewers.voice(&login))
+                        .map(|voice| voice.to_owned());
+
+                      match tts_filter::sanitise(
+                        input,
+                        &chat_filters,
+                        &emote_names,
+                        &config.tts.filter,
+                      ) {
+                        Ok(input) => {
+                          let text = if input.split_whitespace().count() > 1 {
+                            format!("{} says {}", user, input)
+                          } else {
+                            input
+                          };
+                          tts_request = Some(TtsRequest {
+                            username: user.to_owned(),
+                            login: login.to_owned(),
+                            voice,
+                            text,
+                            priority: TtsPriority::Redeem,
+                            redemption: Some(points_redemption.clone()),
+                          });
+                        }
+                        Err(rejection) => {
+                          if let TtsRejection::BlockedTerm(term) = &rejection {
+                            println!("Refused TTS from {}, blocked term: {}", user, term);
+                          }
+                          outcome = RedeemOutcome::Failed(format!(
+                            "your TTS can't be read because {}",
+                            rejection
+                          ));
+                        }
+                      }
+                    }
+                    RewardAction::RunCommand { .. } | RewardAction::PlaySound { .. } => {
+                      if let Err(e) = action.spawn(&user, &input) {
+                        error!("Failed to start {:?}: {}", action, e);
+                        outcome =
+                          RedeemOutcome::Failed("the reward couldn't be started".to_owned());
+                      }
+                    }
+                    RewardAction::StartEffect { name, seconds } => {
+                      // Effect names are posted to chat when they wear off
+                      let effect_input = if name.contains("{input}") {
+                        tts_filter::sanitise(
+                          &input,
+                          &chat_filters,
+                          &emote_names,
+                          &config.tts.filter,
+                        )
+                      } else {
+                        Ok(input.to_owned())
+                      };
+                      match effect_input {
+                        Ok(effect_input) => effects.start(
+                          rewards::fill_template(name, &user, &effect_input),
+                          user.to_owned(),
+                          Duration::from_secs(*seconds),
+                          Instant::now(),
+                        ),
+                        Err(rejection) => {
+                          if let TtsRejection::BlockedTerm(term) = &rejection {
+                            println!("Refused effect from {}, blocked term: {}", user, term);
+                          }
+                          outcome = RedeemOutcome::Failed(format!(
+                            "your effect can't be started because {}",
+                            rejection
+                          ));
+                        }
+                      }
+                    }
+                    RewardAction::IncrementCounter {
+                      counter: counter_name,
+                    } => {
+                      let count = counter.increment(counter_name);
+                      counter.save();
+                      println!("{} is now {}", counter_name, count);
+                    }
                   }
+                }
 
-                  if title.contains("Feed the fimsh") {
-                    *viewer_num += 1;
-                    points = 1;
-                  }
-                  if title.contains("Feed the fimsh! x10") {
-                    *viewer_num += 8;
-                    points = 8;
+                if points != 0 {
+                  let mut file = fs::File::create(RANK_BUFFER_FILE).unwrap(); //options()
+                                                                              // .append(false)
+                                                                              //   .create(true)
+                                                                              // .open(RANK_BUFFER_FILE)
+                                                                              //.unwrap();
+                  let mut rank_buffer_string = String::new();
+                  for (key, value) in rank_buffer.iter() {
+                    rank_buffer_string = format!("{}\n{} {}", rank_buffer_string, key, value);
                   }
 
-                  if points != 0 {
-                    let mut file = fs::File::create(RANK_BUFFER_FILE).unwrap(); //options()
-                                                                                // .append(false)
-                                                                                //   .create(true)
-                                                                                // .open(RANK_BUFFER_FILE)
-                                                                                //.unwrap();
-                    let mut rank_buffer_string = String::new();
-                    for (key, value) in rank_buffer.iter() {
-                      rank_buffer_string = format!("{}\n{} {}", rank_buffer_string, key, value);
-                    }
+                  file
+                    .write_all(format!("{}\n", rank_buffer_string).as_bytes())
+                    .unwrap();
+                }
 
-                    file
-                      .write_all(format!("{}\n", rank_buffer_string).as_bytes())
-                      .unwrap();
+                if great_fimsh_points != 0 {
+                  if let Some(great_fimsh_number) = rank_buffer.get_mut(THE_GREAT_FIMSH) {
+                    *great_fimsh_number =
+                      (*great_fimsh_number as i32 + great_fimsh_points).max(0) as u32;
+                    let great_fimsh_p =
+                      format!("The great fimsh now possesses {}P", great_fimsh_number);
+                    let _ = twitch.send_chat_message(great_fimsh_p.to_owned());
+                    println!("{}", great_fimsh_p);
                   }
+                }
 
-                  if great_fimsh_points != 0 {
-                    if let Some(great_fimsh_number) = rank_buffer.get_mut(THE_GREAT_FIMSH) {
-                      *great_fimsh_number =
-                        (*great_fimsh_number as i32 + great_fimsh_points).max(0) as u32;
-                      let great_fimsh_p =
-                        format!("The great fimsh now possesses {}P", great_fimsh_number);
-                      let _ = twitch.send_chat_message(great_fimsh_p.to_owned());
-                      println!("{}", great_fimsh_p);
+                // Only queued once every other action has worked, the queue
+                // resolves the redeem once it has been read out
+                if let Some(request) = tts_request {
+                  if !matches!(outcome, RedeemOutcome::Failed(_)) {
+                    if config.tts.filter.approve_first_redeems
+                      && !viewers
+                        .get(&login)
+                        .is_some_and(|viewer| viewer.tts_approved)
+                    {
+                      tts_queue.hold(request);
+                    } else {
+                      tts_queue.push(request);
                     }
+                    outcome = RedeemOutcome::Pending;
                   }
                 }
+
+                points_redemption.resolve(&mut twitch, outcome);
               }
               Event::NewSubscription(subscription) => {
-                if subscription.is_gift {
-                  println!(
+                // Gifted subs are thanked through the person who gifted them
+                if !subscription.is_gift {
+                  config.thanks.thank(
+                    &mut twitch,
+                    ThanksKind::Subscription,
+                    &subscription.user.name,
+                    1,
+                    &subscription.tier,
+                  );
+                }
+                let text = if subscription.is_gift {
+                  format!(
                     "{} received their first tier {} subscription!",
                     subscription.user.name, subscription.tier
-                  );
+                  )
                 } else {
-                  println!(
+                  format!(
                     "{} subscribed for the first time with a tier {} sub!",
                     subscription.user.name, subscription.tier
-                  );
-                }
+                  )
+                };
+                alerts.push(
+                  Alert::new(
+                    AlertKind::Subscription,
+                    text,
+                    OverlayEvent::Subscription {
+                      user: subscription.user.name.to_owned(),
+                      tier: subscription.tier.to_owned(),
+                      months: 1,
+                      gifted: subscription.is_gift,
+                      message: None,
+                    },
+                  ),
+                  Instant::now(),
+                );
               }
               Event::GiftSubscription(gifty) => {
-                println!(
-                  "{} Generously Gifted {} tier {} subscriptions!",
-                  gifty.user.name.unwrap_or("Anonymous".to_owned()),
+                config.thanks.thank(
+                  &mut twitch,
+                  ThanksKind::Gift,
+                  gifty.user.name.as_deref().unwrap_or("Anonymous"),
                   gifty.total,
-                  gifty.tier
+                  &gifty.tier,
+                );
+                alerts.push(
+                  Alert::new(
+                    AlertKind::GiftSubs,
+                    format!(
+                      "{} Generously Gifted {} tier {} subscriptions!",
+                      gifty.user.name.clone().unwrap_or("Anonymous".to_owned()),
+                      gifty.total,
+                      gifty.tier
+                    ),
+                    OverlayEvent::GiftSubs {
+                      user: gifty.user.name.to_owned(),
+                      count: gifty.total,
+                      tier: gifty.tier.to_owned(),
+                      recipients: Vec::new(),
+                    },
+                  ),
+                  Instant::now(),
                 );
               }
               Event::Resubscription(subscription) => {
-                println!(
-                  "{} has resubscribed for {} months total!",
-                  subscription.user.name, subscription.cumulative_months
+                config.thanks.thank(
+                  &mut twitch,
+                  ThanksKind::Resubscription,
+                  &subscription.user.name,
+                  subscription.cumulative_months,
+                  &subscription.tier,
+                );
+                let tts = tts_filter::sanitise(
+                  &subscription.message.text,
+                  &chat_filters,
+                  &emote_names,
+                  &config.tts.filter,
+                )
+                .ok()
+                .map(|text| TtsRequest {
+                  username: subscription.user.name.to_owned(),
+                  login: subscription.user.login.to_owned(),
+                  voice: viewers
+                    .voice(&subscription.user.login)
+                    .map(|voice| voice.to_owned()),
+                  text: format!("{} says {}", subscription.user.name, text),
+                  priority: TtsPriority::Support,
+                  redemption: None,
+                });
+                alerts.push(
+                  Alert::new(
+                    AlertKind::Subscription,
+                    format!(
+                      "{} has resubscribed for {} months total!\n    {}",
+                      subscription.user.name,
+                      subscription.cumulative_months,
+                      subscription.message.text
+                    ),
+                    OverlayEvent::Subscription {
+                      user: subscription.user.name.to_owned(),
+                      tier: subscription.tier.to_owned(),
+                      months: subscription.cumulative_months,
+                      gifted: false,
+                      message: Some(overlay::viewer_text(
+                        &subscription.message.text,
+                        &chat_filters,
+                      )),
+                    },
+                  )
+                  .with_tts(tts),
+                  Instant::now(),
                 );
-                println!("    {}", subscription.message.text);
               }
               Event::Cheer(cheer) => {
-                println!("{} cheered with {} bits!", cheer.user.name, cheer.bits);
+                let cheerer = if cheer.is_anonymous {
+                  "Anonymous".to_owned()
+                } else {
+                  cheer.user.name.to_owned()
+                };
+                config
+                  .thanks
+                  .thank(&mut twitch, ThanksKind::Cheer, &cheerer, cheer.bits, "");
+                let tts = tts_filter::sanitise(
+                  &cheer.message,
+                  &chat_filters,
+                  &emote_names,
+                  &config.tts.filter,
+                )
+                .ok()
+                .map(|text| TtsRequest {
+                  username: cheerer.to_owned(),
+                  login: cheer.user.login.to_owned(),
+                  voice: (!cheer.is_anonymous)
+                    .then(|| viewers.voice(&cheer.user.login))
+                    .flatten()
+                    .map(|voice| voice.to_owned()),
+                  text: format!("{} says {}", cheerer, text),
+                  priority: TtsPriority::Support,
+                  redemption: None,
+                });
+                alerts.push(
+                  Alert::new(
+                    AlertKind::Cheer,
+                    format!("{} cheered with {} bits!", cheerer, cheer.bits),
+                    OverlayEvent::Cheer {
+                      user: (!cheer.is_anonymous).then(|| cheer.user.name.to_owned()),
+                      bits: cheer.bits,
+                      message: overlay::viewer_text(&cheer.message, &chat_filters),
+                    },
+                  )
+                  .with_tts(tts),
+                  Instant::now(),
+                );
               }
               Event::HypeTrainBegin(hype_train) => {
-                println!("Train Begin: {:?}", hype_train);
+                hype_trains.begin(&mut twitch, &overlay, &hype_train);
               }
               Event::HypeTrainProgress(train_progress) => {
-                println!("Train Progress: {:?}", train_progress);
+                hype_trains.progress(&mut twitch, &overlay, &train_progress);
               }
               Event::HypeTrainEnd(hype_end) => {
-                println!("The hype train ended at level {}!", hype_end.level);
+                hype_trains.end(&mut twitch, &overlay, &hype_end);
               }
               Event::PredictionBegin(prediction_begin) => {
-                //println!("{:#?}", prediction_begin);
+                polls.prediction_begin(&prediction_begin.id, &prediction_begin.title);
               }
               Event::PredictionProgress(prediction_progress) => {
-                //println!("{:#?}", prediction_progress);
+                polls.prediction_progress(
+                  &prediction_progress.id,
+                  &prediction_progress.title,
+                  &prediction_progress.outcomes,
+                );
               }
               Event::PredictionEnd(prediction_end) => {
-                // println!("{:#?}", prediction_end);
+                polls.prediction_end(
+                  &mut twitch,
+                  &prediction_end.title,
+                  &prediction_end.winning_outcome_id,
+                  &prediction_end.outcomes,
+                  &prediction_end.status,
+                );
               }
               Event::PredictionLock(prediction_lock) => {
-                //println!("{:#?}", prediction_lock);
+                polls.prediction_lock(
+                  &mut twitch,
+                  &prediction_lock.id,
+                  &prediction_lock.title,
+                  &prediction_lock.outcomes,
+                );
               }
               Event::PollBegin(begin_data) => {
-                // println!("{:#?}", begin_data);
+                polls.poll_begin(&begin_data.id, &begin_data.title);
               }
               Event::PollProgress(progress_data) => {
-                //println!("{:#?}", progress_data);
+                polls.poll_progress(&progress_data.title, &progress_data.choices);
               }
               Event::PollEnd(end_data) => {
-                //println!("{:#?}", end_data);
-              }
-              Event::HypeTrainBegin(begin_train) => {}
-              Event::HypeTrainProgress(train_data) => {
-                println!("{:?}", train_data);
-              }
-              Event::HypeTrainEnd(train_data) => {
-                println!("{:?}", train_data);
+                polls.poll_end(
+                  &mut twitch,
+                  &end_data.title,
+                  &end_data.choices,
+                  &end_data.status,
+                );
               }
               Event::ChatMessage(message_data) => {
                 let chat_message = ChatMessage::from(&message_data);
                 let username = message_data.chatter.name;
+                let user_id = message_data.chatter.id.to_owned();
+                let user_login = message_data.chatter.login.to_owned();
                 let message = message_data.message.text;
                 let message_id = message_data.message_id;
 
@@ -865,37 +1325,87 @@ fn main() {
                   _ => {}
                 }
 
+                let is_bot = config.bot_users.is_bot(&username);
+                let is_ignored = config
+                  .bot_users
+                  .has_policy(&username, BotPolicy::IgnoreChatter);
+
+                if config
+                  .bot_users
+                  .has_policy(&username, BotPolicy::ForwardToAlerts)
+                {
+                  alerts.push(
+                    Alert::new(
+                      AlertKind::Bot,
+                      format!("Alert from {}: {}", username, message),
+                      OverlayEvent::BotAlert {
+                        user: username.to_owned(),
+                        message: overlay::viewer_text(&message, &chat_filters),
+                      },
+                    ),
+                    Instant::now(),
+                  );
+                }
+
+                if config
+                  .bot_users
+                  .has_policy(&username, BotPolicy::DeleteFromChat)
+                {
+                  let _ = twitch.delete_message(&message_id);
+                  continue;
+                }
+
                 // First time chatter!
+                let is_new_chatter = (message_data.first_time_chatter
+                  || (!all_messages.contains_key(&username)
+                    && !rank_buffer.contains_key(&username)))
+                  && username.to_lowercase() != STREAM_ACCOUNT;
+                let is_exempt = config.filters.is_exempt(&username, &message_data.badges);
                 let lower_message = message.to_ascii_lowercase();
-                if !all_messages.contains_key(&username)
-                  && !rank_buffer.contains_key(&username)
-                  && username.to_lowercase() != STREAM_ACCOUNT
+                let mut spam_check = SpamCheck::check(&lower_message, &chat_filters, false);
+                // Permits are only used up where the link would have been removed
+                let links_filtered = !is_bot
+                  && (is_new_chatter || (config.filters.filter_links_for_everyone && !is_exempt));
+                if spam_check.has_link
+                  && links_filtered
+                  && permits.use_link(&username, Instant::now())
                 {
-                  let is_link = lower_message
-                    .split('.')
-                    .skip(1)
-                    .any(|s| s.len() > 1 && s.chars().take(2).all(char::is_alphabetic));
-
-                  let sus_words = [
-                    "cheap",
-                    "view",
-                    "streamrise",
-                    "onlyfans",
-                    "http",
-                    "promot",
-                    "activat",
-                    "free",
-                    "hosthub",
-                  ];
-
-                  if is_link
-                    || sus_words
-                      .iter()
-                      .filter(|sussy| lower_message.contains(*sussy))
-                      .count()
-                      > 1
+                  spam_check = SpamCheck::check(&lower_message, &chat_filters, true);
+                }
+                let flagged = FlaggedMessage {
+                  username: username.to_owned(),
+                  user_id: user_id.to_owned(),
+                  message_id: message_id.to_owned(),
+                  text: message.to_owned(),
+                  spam_check,
+                };
+                if is_bot {
+                  // Known bots are trusted
+                } else if is_new_chatter {
+                  if let Some(flood_event) = flood_guard.record(&flagged, Instant::now()) {
+                    flood_guard.handle(&mut twitch, &flood_event);
+                    // Flooding doesn't count towards strikes, raiders and
+                    // copypasta get caught up in it too
+                    for flooded in flood_event.into_messages() {
+                      if strikes.remove(&mut twitch, &flooded) {
+                        bots_recently_vanquished += 1;
+                        time_since_last_vanquish = Instant::now();
+                        past_chat_messages.retain(|c| c.id != flooded.message_id);
+                      }
+                    }
+                    recreate_chat(
+                      message_id,
+                      &mut past_chat_messages,
+                      &mut twitch,
+                      &mut emote_buffer,
+                    );
+                    continue;
+                  }
+
+                  if flagged.spam_check.has_blocked_term
+                    || flagged.spam_check.score >= config.moderation.spam_threshold
                   {
-                    if let Ok(_) = twitch.delete_message(&message_id) {
+                    if strikes.punish(&mut twitch, &config.moderation, &flagged, None) {
                       bots_recently_vanquished += 1;
                       time_since_last_vanquish = Instant::now();
                       recreate_chat(
@@ -908,24 +1418,39 @@ fn main() {
 
                     continue;
                   }
-                }
 
-                if message_data.first_time_chatter {
-                  print!("FTC: ");
-                }
-                if message_data.returning_chatter {
-                  print!("RC: ");
-                }
-                if message_data.moderator {
-                  print!("Cute moderator -> ");
+                  if flagged.spam_check.score >= config.moderation.review_threshold {
+                    review_queue.hold(flagged, chat_message);
+                    continue;
+                  }
+                } else if !is_exempt
+                  && (flagged.spam_check.has_blocked_term
+                    || (config.filters.filter_links_for_everyone && flagged.spam_check.has_link))
+                {
+                  // Known chatters only lose the message, strikes are for bots
+                  if strikes.remove(&mut twitch, &flagged) {
+                    println!("Removed a message from {}", username);
+                  }
+                  continue;
                 }
 
-                if username == AUDIO_ALERT_BOT {
-                  if let Ok(_) = twitch.delete_message(&message_id) {}
-                  continue;
+                let is_hidden = config
+                  .bot_users
+                  .has_policy(&username, BotPolicy::HideFromTerminal);
+
+                if !is_hidden {
+                  if message_data.first_time_chatter && !is_ignored {
+                    print!("FTC: ");
+                  }
+                  if message_data.returning_chatter && !is_ignored {
+                    print!("RC: ");
+                  }
+                  if message_data.moderator {
+                    print!("Cute moderator -> ");
+                  }
                 }
 
-                if username.to_lowercase() != STREAM_ACCOUNT {
+                if username.to_lowercase() != STREAM_ACCOUNT && !is_hidden {
                   chat_message.print(&mut twitch, &mut emote_buffer);
                   past_chat_messages.push(chat_message);
                   if past_chat_messages.len() > 20 {
@@ -934,16 +1459,19 @@ fn main() {
                 }
 
                 // comment
-                all_messages
-                  .entry(username.clone())
-                  .and_modify(|msg: &mut Vec<String>| msg.push(message.clone()))
-                  .or_insert(vec![message.clone()]);
+                if !is_ignored {
+                  all_messages
+                    .entry(username.clone())
+                    .and_modify(|msg: &mut Vec<String>| msg.push(message.clone()))
+                    .or_insert(vec![message.clone()]);
+                }
 
                 let possible_quote = message.to_ascii_lowercase();
                 if (possible_quote.contains("don't quote")
                   || possible_quote.contains("dont quote")
                   || possible_quote.contains("do not quote"))
                   && username.to_lowercase() != STREAM_ACCOUNT
+                  && !is_ignored
                 {
                   if let Some(msgs) = all_messages.get(&username) {
                     let quote = if msgs.len() > 1 {
@@ -969,9 +1497,11 @@ fn main() {
                   }
                 }
 
+                // Polls and predictions keep the case of their options
+                let original_text = message.to_owned();
                 let message = message.to_ascii_lowercase();
 
-                if !rank_buffer.contains_key(&username) {
+                if !is_ignored && !rank_buffer.contains_key(&username) {
                   rank_buffer.insert(username.to_owned(), 0);
                 }
 
@@ -983,6 +1513,8 @@ fn main() {
                 }
 
                 let is_commmand = message.as_bytes()[0] as char == '!';
+                let is_moderator =
+                  message_data.moderator || username.to_lowercase() == STREAM_ACCOUNT;
 
                 if !is_commmand && username.to_lowercase() != STREAM_ACCOUNT {
                   if message.contains("holee") | message.contains("holy") {
@@ -1170,7 +1702,8 @@ fn main() {
                             if moderators.contains(&username)
                               || username.eq_ignore_ascii_case(STREAM_ACCOUNT)
                             {
-                              shoutout(&mut twitch, parameters[0].to_owned());
+                              let streamer = parameters[0].trim_start_matches('@');
+                              shoutout(&mut twitch, streamer, &streamer.to_lowercase(), None);
                             }
                           }
                         }
@@ -1337,6 +1870,217 @@ fn main() {
                             counter.save();
                           }
                         }
+                        ChatCommands::ModLog => {
+                          if is_moderator {
+                            let count = parameters
+                              .first()
+                              .and_then(|count| count.parse::<usize>().ok())
+                              .unwrap_or(3)
+                              .min(5);
+                            let entries = modlog::recent(count);
+                            let modlog_text = if entries.is_empty() {
+                              "Owlkatraz is empty!".to_owned()
+                            } else {
+                              entries
+                                .iter()
+                                .map(ModLogEntry::chat_summary)
+                                .collect::<Vec<_>>()
+                                .join(" | ")
+                            };
+                            let _ = twitch.send_chat_message_with_reply(
+                              modlog_text.chars().take(500).collect::<String>(),
+                              Some(message_id),
+                            );
+                          }
+                        }
+                        ChatCommands::Approve | ChatCommands::Nuke => {
+                          let held_id = parameters.first().and_then(|id| id.parse::<u32>().ok());
+                          if let (true, Some(id)) = (is_moderator, held_id) {
+                            let decision = if command == ChatCommands::Approve {
+                              ReviewDecision::Approve
+                            } else {
+                              ReviewDecision::Delete
+                            };
+                            review_decisions.push((decision, id));
+                          }
+                        }
+                        ChatCommands::Block | ChatCommands::Unblock | ChatCommands::AllowLink => {
+                          if is_moderator && !parameters.is_empty() {
+                            let term = parameters.join(" ");
+                            // Blocked terms are never repeated back into chat
+                            let filter_text = match command {
+                              ChatCommands::Block if chat_filters.block(&term) => {
+                                "That term is now blocked.".to_owned()
+                              }
+                              ChatCommands::Unblock if chat_filters.unblock(&term) => {
+                                "That term is no longer blocked.".to_owned()
+                              }
+                              ChatCommands::AllowLink if chat_filters.allow_link(&term) => {
+                                format!("Links to {} are now allowed.", term)
+                              }
+                              _ => "Nothing changed.".to_owned(),
+                            };
+                            let _ = twitch.send_chat_message_with_reply(
+                              filter_text.to_owned(),
+                              Some(message_id),
+                            );
+                            println!("{} ({:?} {})", filter_text, command, term);
+                          }
+                        }
+                        ChatCommands::Permit => {
+                          if is_moderator && !parameters.is_empty() {
+                            let permitted = parameters[0].trim_start_matches('@');
+                            let seconds = parameters
+                              .get(1)
+                              .and_then(|seconds| seconds.parse::<u64>().ok());
+                            permits.grant(permitted, seconds, Instant::now());
+                            let permit_text = match seconds {
+                              Some(seconds) => {
+                                format!("{} may post links for {} seconds.", permitted, seconds)
+                              }
+                              None => format!("{} may post one link.", permitted),
+                            };
+                            let _ = twitch.send_chat_message(permit_text);
+                          }
+                        }
+                        ChatCommands::Tts => {
+                          if is_moderator {
+                            let words = parameters
+                              .iter()
+                              .map(|word| word.as_str())
+                              .collect::<Vec<_>>();
+                            let tts_text = match TtsControl::parse(&words) {
+                              Some(control) => {
+                                tts_queue.control(&mut twitch, &mut viewers, control)
+                              }
+                              None => {
+                                "Usage: !tts skip|pause|resume|clear|queue|approve <id>|reject <id>"
+                                  .to_owned()
+                              }
+                            };
+                            let _ = twitch
+                              .send_chat_message_with_reply(tts_text.to_owned(), Some(message_id));
+                            println!("{}", tts_text);
+                          }
+                        }
+                        ChatCommands::Poll | ChatCommands::Predict => {
+                          if is_moderator {
+                            let arguments = original_text
+                              .split_once(char::is_whitespace)
+                              .map(|(_, arguments)| arguments)
+                              .unwrap_or("");
+                            let vote_text = if command == ChatCommands::Poll {
+                              polls.poll_command(&twitch, arguments)
+                            } else {
+                              polls.predict_command(&twitch, arguments)
+                            };
+                            let _ = twitch.send_chat_message_with_reply(
+                              vote_text.to_owned(),
+                              Some(message_id.to_owned()),
+                            );
+                            println!("{}", vote_text);
+                          }
+                        }
+                        ChatCommands::FollowAge => {
+                          let follower = parameters
+                            .first()
+                            .map(|name| name.trim_start_matches('@').to_lowercase())
+                            .unwrap_or(user_login.to_owned());
+                          let mut followed_at =
+                            viewers.get(&follower).and_then(|viewer| viewer.followed_at);
+                          if followed_at.is_none() {
+                            let follower_id = if follower == user_login {
+                              Some(user_id.to_owned())
+                            } else {
+                              twitch
+                                .get_users_from_logins(vec![follower.to_owned()])
+                                .ok()
+                                .and_then(|users| users.data.first().map(|user| user.id.to_owned()))
+                            };
+                            followed_at = follower_id
+                              .and_then(|id| helix::get_followed_at(&twitch, id).ok().flatten())
+                              .and_then(|followed_at| clock::parse_timestamp(&followed_at));
+                            if followed_at.is_some() {
+                              viewers.get_mut(&follower).followed_at = followed_at;
+                              viewers.save();
+                            }
+                          }
+
+                          let followage_text = match followed_at {
+                            Some(followed_at) => followers::followage_text(
+                              &follower,
+                              followed_at,
+                              clock::unix_timestamp(),
+                            ),
+                            None => format!("{} isn't following yet.", follower),
+                          };
+                          let _ =
+                            twitch.send_chat_message_with_reply(followage_text, Some(message_id));
+                        }
+                        ChatCommands::Effects => {
+                          let _ = twitch.send_chat_message_with_reply(
+                            effects.summary(Instant::now()),
+                            Some(message_id),
+                          );
+                        }
+                        ChatCommands::Rewards => {
+                          if is_moderator {
+                            let rewards_text =
+                              match parameters.split_first().and_then(|(toggle, name)| {
+                                Some((RewardToggle::parse(toggle)?, name.join(" ")))
+                              }) {
+                                Some((toggle, name)) if !name.is_empty() => {
+                                  match rewards::toggle_reward(
+                                    &mut twitch,
+                                    &reward_registry,
+                                    &name,
+                                    toggle,
+                                  ) {
+                                    Ok(text) | Err(text) => text,
+                                  }
+                                }
+                                _ => {
+                                  "Usage: !rewards pause|resume|enable|disable <name>".to_owned()
+                                }
+                              };
+                            let _ = twitch.send_chat_message_with_reply(
+                              rewards_text.to_owned(),
+                              Some(message_id),
+                            );
+                            println!("{}", rewards_text);
+                          }
+                        }
+                        ChatCommands::Voice => {
+                          let voice_text = match parameters.first() {
+                            Some(voice) if tts_voices.contains(voice) => {
+                              viewers.get_mut(&user_login).voice = Some(voice.to_owned());
+                              viewers.save();
+                              format!("Your TTS voice is now {}.", voice)
+                            }
+                            _ => format!(
+                              "Pick a TTS voice with !voice <name>: {}",
+                              tts_voices.names().join(", ")
+                            ),
+                          };
+                          let _ = twitch
+                            .send_chat_message_with_reply(voice_text.to_owned(), Some(message_id));
+                        }
+                        ChatCommands::Pardon => {
+                          if is_moderator && !parameters.is_empty() {
+                            let pardoned = parameters[0].trim_start_matches('@');
+                            let pardon_text = if strikes.pardon(pardoned) {
+                              strikes.save();
+                              format!("{} has been pardoned, their strikes are cleared.", pardoned)
+                            } else {
+                              format!("{} doesn't have any strikes.", pardoned)
+                            };
+                            let _ = twitch.send_chat_message_with_reply(
+                              pardon_text.to_owned(),
+                              Some(message_id),
+                            );
+                            println!("{}", pardon_text);
+                          }
+                        }
                       }
                     }
                     (None, Some(close), _) => {
@@ -0,0 +1,244 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::{error, TwitchEventSubApi};
+
+use crate::modules::{alerts::AlertQueue, clock::unix_timestamp, templates, tts_queue::TtsQueue};
+
+// Templates can use {duration}, e.g. "3 minute" or "90 second"
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct AdConfig {
+  pub announcement: String,
+  pub back: String,
+  // Set to null to stop warning chat before scheduled ads
+  pub warning_seconds: Option<u64>,
+  pub warning: String,
+  // How often the ad schedule is fetched
+  pub schedule_check_seconds: u64,
+}
+
+impl Default for AdConfig {
+  fn default() -> Self {
+    AdConfig {
+      announcement: "A {duration} Ad has attacked! I try my best to not do anything interesting."
+        .to_owned(),
+      back: "The Ad has been defeated, welcome back!".to_owned(),
+      warning_seconds: Some(60),
+      warning: "A {duration} Ad is approaching, now is a good time to grab a drink!".to_owned(),
+      schedule_check_seconds: 5 * 60,
+    }
+  }
+}
+
+fn describe_duration(seconds: u32) -> String {
+  if seconds.is_multiple_of(60) {
+    format!("{} minute", seconds / 60)
+  } else {
+    format!("{} second", seconds)
+  }
+}
+
+// Callers pass in the current instant so the clock can be faked
+pub struct AdBreaks {
+  config: AdConfig,
+  ends_at: Option<Instant>,
+  // Only resumed after the break if the ad was what paused them
+  paused_tts: bool,
+  paused_alerts: bool,
+  next_ad_at: Option<u64>,
+  next_ad_duration: u32,
+  warned_for: Option<u64>,
+  last_schedule_check: Option<Instant>,
+}
+
+impl AdBreaks {
+  pub fn new(config: AdConfig) -> AdBreaks {
+    AdBreaks {
+      config,
+      ends_at: None,
+      paused_tts: false,
+      paused_alerts: false,
+      next_ad_at: None,
+      next_ad_duration: 0,
+      warned_for: None,
+      last_schedule_check: None,
+    }
+  }
+
+  pub fn begin(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    tts_queue: &mut TtsQueue,
+    alerts: &mut AlertQueue,
+    duration_seconds: u32,
+    now: Instant,
+  ) {
+    let announcement = templates::render(
+      &self.config.announcement,
+      &[("duration", &describe_duration(duration_seconds))],
+    );
+    let _ = twitch.send_chat_message(announcement.to_owned());
+    println!("{}", announcement);
+
+    self.pause(tts_queue, alerts, duration_seconds, now);
+  }
+
+  fn pause(
+    &mut self,
+    tts_queue: &mut TtsQueue,
+    alerts: &mut AlertQueue,
+    duration_seconds: u32,
+    now: Instant,
+  ) {
+    // A break that starts during another just extends it
+    self.paused_tts |= tts_queue.pause();
+    self.paused_alerts |= alerts.pause();
+    self.ends_at = Some(now + Duration::from_secs(duration_seconds as u64));
+    self.next_ad_at = None;
+  }
+
+  // Returns true when the break has just ended
+  fn resume(&mut self, tts_queue: &mut TtsQueue, alerts: &mut AlertQueue, now: Instant) -> bool {
+    if self.ends_at.is_none_or(|ends_at| ends_at > now) {
+      return false;
+    }
+
+    self.ends_at = None;
+    if std::mem::take(&mut self.paused_tts) {
+      tts_queue.resume();
+    }
+    if std::mem::take(&mut self.paused_alerts) {
+      alerts.resume();
+    }
+    true
+  }
+
+  pub fn tick(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    tts_queue: &mut TtsQueue,
+    alerts: &mut AlertQueue,
+    now: Instant,
+  ) {
+    if self.ends_at.is_some_and(|ends_at| ends_at > now) {
+      return;
+    }
+
+    if self.resume(tts_queue, alerts, now) {
+      let _ = twitch.send_chat_message(self.config.back.to_owned());
+      println!("{}", self.config.back);
+      // The schedule moves on once an ad has played
+      self.last_schedule_check = None;
+    }
+
+    let Some(warning_seconds) = self.config.warning_seconds else {
+      return;
+    };
+
+    self.check_schedule(twitch, now);
+
+    let Some(next_ad_at) = self.next_ad_at else {
+      return;
+    };
+    if self.warned_for == Some(next_ad_at)
+      || unix_timestamp() + warning_seconds < next_ad_at
+      || unix_timestamp() > next_ad_at
+    {
+      return;
+    }
+
+    self.warned_for = Some(next_ad_at);
+    let warning = templates::render(
+      &self.config.warning,
+      &[("duration", &describe_duration(self.next_ad_duration))],
+    );
+    let _ = twitch.send_chat_message(warning.to_owned());
+    println!("{}", warning);
+  }
+
+  fn check_schedule(&mut self, twitch: &mut TwitchEventSubApi, now: Instant) {
+    if self.last_schedule_check.is_some_and(|checked| {
+      now.duration_since(checked) < Duration::from_secs(self.config.schedule_check_seconds)
+    }) {
+      return;
+    }
+    self.last_schedule_check = Some(now);
+
+    match twitch.get_ad_schedule() {
+      Ok(schedule) => {
+        let next_ad = schedule.data.first();
+        // Twitch sends 0 when there is no ad scheduled
+        self.next_ad_at = next_ad
+          .map(|ad| ad.next_ad_at as u64)
+          .filter(|next_ad_at| *next_ad_at > 0);
+        self.next_ad_duration = next_ad.map(|ad| ad.duration).unwrap_or(0);
+      }
+      Err(e) => {
+        error!(
+          "Failed to get the ad schedule, retrying in {}s: {:?}",
+          self.config.schedule_check_seconds, e
+        );
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::alerts::AlertConfig;
+
+  #[test]
+  fn break_pauses_and_resumes_queues() {
+    let start = Instant::now();
+    let mut ads = AdBreaks::new(AdConfig::default());
+    let mut tts_queue = TtsQueue::default();
+    let mut alerts = AlertQueue::new(AlertConfig::default());
+
+    ads.pause(&mut tts_queue, &mut alerts, 90, start);
+    assert!(!ads.resume(&mut tts_queue, &mut alerts, start + Duration::from_secs(89)));
+    assert!(ads.resume(&mut tts_queue, &mut alerts, start + Duration::from_secs(90)));
+    assert!(!ads.resume(&mut tts_queue, &mut alerts, start + Duration::from_secs(91)));
+
+    // Both were resumed, so pausing them again succeeds
+    assert!(tts_queue.pause());
+    assert!(alerts.pause());
+  }
+
+  #[test]
+  fn break_leaves_queues_paused_by_someone_else() {
+    let start = Instant::now();
+    let mut ads = AdBreaks::new(AdConfig::default());
+    let mut tts_queue = TtsQueue::default();
+    let mut alerts = AlertQueue::new(AlertConfig::default());
+    tts_queue.pause();
+
+    ads.pause(&mut tts_queue, &mut alerts, 60, start);
+    assert!(ads.resume(&mut tts_queue, &mut alerts, start + Duration::from_secs(60)));
+
+    assert!(!tts_queue.pause());
+    assert!(alerts.pause());
+  }
+
+  #[test]
+  fn overlapping_break_extends_the_first() {
+    let start = Instant::now();
+    let mut ads = AdBreaks::new(AdConfig::default());
+    let mut tts_queue = TtsQueue::default();
+    let mut alerts = AlertQueue::new(AlertConfig::default());
+
+    ads.pause(&mut tts_queue, &mut alerts, 60, start);
+    ads.pause(
+      &mut tts_queue,
+      &mut alerts,
+      60,
+      start + Duration::from_secs(30),
+    );
+
+    assert!(!ads.resume(&mut tts_queue, &mut alerts, start + Duration::from_secs(60)));
+    assert!(ads.resume(&mut tts_queue, &mut alerts, start + Duration::from_secs(90)));
+    assert!(tts_queue.pause());
+    assert!(alerts.pause());
+  }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+
+use crate::modules::{
+  overlay::{OverlayEvent, OverlayServer},
+  tts_queue::{TtsQueue, TtsRequest},
+};
+
+// Later kinds are shown before earlier ones when both are waiting
+#[derive(Serialise, Deserialise, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+  Follow,
+  Bot,
+  Subscription,
+  Cheer,
+  GiftSubs,
+  Raid,
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct AlertConfig {
+  // How long each kind of alert stays up before the next one is shown
+  pub durations: HashMap<AlertKind, u64>,
+  pub default_seconds: u64,
+  // Gifts are held this long for their recipients to arrive, and
+  // recipients this long in case their gift event arrives late
+  pub gift_wait_seconds: u64,
+}
+
+impl Default for AlertConfig {
+  fn default() -> Self {
+    AlertConfig {
+      durations: HashMap::from([
+        (AlertKind::Follow, 3),
+        (AlertKind::Bot, 4),
+        (AlertKind::Raid, 10),
+        (AlertKind::GiftSubs, 8),
+      ]),
+      default_seconds: 6,
+      gift_wait_seconds: 5,
+    }
+  }
+}
+
+impl AlertConfig {
+  fn duration(&self, kind: AlertKind) -> Duration {
+    Duration::from_secs(*self.durations.get(&kind).unwrap_or(&self.default_seconds))
+  }
+}
+
+pub struct Alert {
+  pub kind: AlertKind,
+  // Printed to the terminal when the alert is shown
+  pub text: String,
+  pub event: OverlayEvent,
+  // Spoken once the alert is shown so it lines up with the overlay
+  pub tts: Option<TtsRequest>,
+}
+
+impl Alert {
+  pub fn new<S: Into<String>>(kind: AlertKind, text: S, event: OverlayEvent) -> Alert {
+    Alert {
+      kind,
+      text: text.into(),
+      event,
+      tts: None,
+    }
+  }
+
+  pub fn with_tts(mut self, tts: Option<TtsRequest>) -> Alert {
+    self.tts = tts;
+    self
+  }
+
+  fn gift_recipient(&self) -> Option<&str> {
+    match &self.event {
+      OverlayEvent::Subscription {
+        user, gifted: true, ..
+      } => Some(user),
+      _ => None,
+    }
+  }
+}
+
+struct QueuedAlert {
+  alert: Alert,
+  ready_at: Instant,
+  // Recipients of this gift that haven't arrived yet
+  missing_recipients: u32,
+}
+
+impl QueuedAlert {
+  fn new(alert: Alert, ready_at: Instant) -> QueuedAlert {
+    QueuedAlert {
+      alert,
+      ready_at,
+      missing_recipients: 0,
+    }
+  }
+
+  // The gift is shown straight away once all its recipients are in
+  fn add_recipient(&mut self, user: String, now: Instant) {
+    if let OverlayEvent::GiftSubs { recipients, .. } = &mut self.alert.event {
+      recipients.push(user);
+    }
+    self.missing_recipients = self.missing_recipients.saturating_sub(1);
+    if self.missing_recipients == 0 {
+      self.ready_at = self.ready_at.min(now);
+    }
+  }
+}
+
+// Paces alerts so a burst of events doesn't scroll everything away, callers
+// pass in the current instant so the clock can be faked.
+pub struct AlertQueue {
+  config: AlertConfig,
+  queued: Vec<QueuedAlert>,
+  showing_until: Option<Instant>,
+  paused: bool,
+}
+
+impl AlertQueue {
+  pub fn new(config: AlertConfig) -> AlertQueue {
+    AlertQueue {
+      config,
+      queued: Vec::new(),
+      showing_until: None,
+      paused: false,
+    }
+  }
+
+  // Twitch sends a gift's recipients one after another, so each recipient
+  // belongs to the oldest gift that is still missing some
+  pub fn push(&mut self, alert: Alert, now: Instant) {
+    let gift_wait = Duration::from_secs(self.config.gift_wait_seconds);
+
+    if let Some(recipient) = alert.gift_recipient() {
+      let recipient = recipient.to_owned();
+      match self
+        .queued
+        .iter_mut()
+        .find(|queued| queued.missing_recipients > 0)
+      {
+        Some(gift) => gift.add_recipient(recipient, now),
+        None => self.queued.push(QueuedAlert::new(alert, now + gift_wait)),
+      }
+      return;
+    }
+
+    if let OverlayEvent::GiftSubs { count, .. } = &alert.event {
+      let mut gift = QueuedAlert {
+        missing_recipients: *count,
+        ..QueuedAlert::new(alert, now + gift_wait)
+      };
+      // Recipients that arrived before the gift itself
+      let mut idx = 0;
+      while idx < self.queued.len() && gift.missing_recipients > 0 {
+        match self.queued[idx].alert.gift_recipient() {
+          Some(recipient) => {
+            let recipient = recipient.to_owned();
+            self.queued.remove(idx);
+            gift.add_recipient(recipient, now);
+          }
+          None => idx += 1,
+        }
+      }
+      self.queued.push(gift);
+      return;
+    }
+
+    self.queued.push(QueuedAlert::new(alert, now));
+  }
+
+  // Alerts keep queueing while paused and are shown once resumed
+  pub fn pause(&mut self) -> bool {
+    let was_paused = self.paused;
+    self.paused = true;
+    !was_paused
+  }
+
+  pub fn resume(&mut self) -> bool {
+    let was_paused = self.paused;
+    self.paused = false;
+    was_paused
+  }
+
+  // Takes the next alert once the current one has been up long enough
+  pub fn next(&mut self, now: Instant) -> Option<Alert> {
+    if self.paused || self.showing_until.is_some_and(|until| until > now) {
+      return None;
+    }
+
+    let idx = self
+      .queued
+      .iter()
+      .enumerate()
+      .filter(|(_, queued)| queued.ready_at <= now)
+      // Highest priority first, then whichever has waited longest
+      .min_by_key(|(idx, queued)| (std::cmp::Reverse(queued.alert.kind), *idx))
+      .map(|(idx, _)| idx)?;
+
+    let alert = self.queued.remove(idx).alert;
+    self.showing_until = Some(now + self.config.duration(alert.kind));
+    Some(alert)
+  }
+
+  pub fn tick(&mut self, overlay: &OverlayServer, tts_queue: &mut TtsQueue, now: Instant) {
+    let Some(mut alert) = self.next(now) else {
+      return;
+    };
+
+    println!("{}", alert.text);
+    if let OverlayEvent::GiftSubs { recipients, .. } = &alert.event {
+      if !recipients.is_empty() {
+        println!("    to {}", recipients.join(", "));
+      }
+    }
+    if let Some(tts) = alert.tts.take() {
+      tts_queue.push(tts);
+    }
+    overlay.send(alert.event);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gift(user: &str, count: u32) -> Alert {
+    Alert::new(
+      AlertKind::GiftSubs,
+      format!("{} gifted {} subs!", user, count),
+      OverlayEvent::GiftSubs {
+        user: Some(user.to_owned()),
+        count,
+        tier: "1000".to_owned(),
+        recipients: Vec::new(),
+      },
+    )
+  }
+
+  fn recipient(user: &str) -> Alert {
+    Alert::new(
+      AlertKind::Subscription,
+      format!("{} subscribed!", user),
+      OverlayEvent::Subscription {
+        user: user.to_owned(),
+        tier: "1000".to_owned(),
+        months: 1,
+        gifted: true,
+        message: None,
+      },
+    )
+  }
+
+  fn recipients(alert: &Alert) -> Vec<String> {
+    match &alert.event {
+      OverlayEvent::GiftSubs { recipients, .. } => recipients.clone(),
+      _ => Vec::new(),
+    }
+  }
+
+  #[test]
+  fn gift_waits_for_its_recipients() {
+    let start = Instant::now();
+    let mut alerts = AlertQueue::new(AlertConfig::default());
+    alerts.push(gift("owl", 2), start);
+
+    assert!(alerts.next(start).is_none());
+    alerts.push(recipient("a"), start);
+    assert!(alerts.next(start).is_none());
+    alerts.push(recipient("b"), start);
+
+    let shown = alerts.next(start).unwrap();
+    assert_eq!(recipients(&shown), ["a", "b"]);
+    assert!(alerts.next(start + Duration::from_secs(60)).is_none());
+  }
+
+  #[test]
+  fn gift_is_shown_after_waiting_for_late_recipients() {
+    let start = Instant::now();
+    let mut alerts = AlertQueue::new(AlertConfig::default());
+    alerts.push(gift("owl", 3), start);
+    alerts.push(recipient("a"), start);
+
+    assert!(alerts.next(start + Duration::from_secs(4)).is_none());
+    let shown = alerts.next(start + Duration::from_secs(5)).unwrap();
+    assert_eq!(recipients(&shown), ["a"]);
+  }
+
+  #[test]
+  fn recipients_go_to_their_own_gift() {
+    let start = Instant::now();
+    let mut alerts = AlertQueue::new(AlertConfig::default());
+    alerts.push(gift("owl", 1), start);
+    alerts.push(gift("kal", 2), start);
+    for user in ["a", "b", "c"] {
+      alerts.push(recipient(user), start);
+    }
+
+    assert_eq!(recipients(&alerts.next(start).unwrap()), ["a"]);
+    let later = start + Duration::from_secs(60);
+    assert_eq!(recipients(&alerts.next(later).unwrap()), ["b", "c"]);
+  }
+
+  #[test]
+  fn early_recipients_are_folded_into_the_gift() {
+    let start = Instant::now();
+    let mut alerts = AlertQueue::new(AlertConfig::default());
+    alerts.push(recipient("a"), start);
+    alerts.push(gift("owl", 1), start);
+
+    assert_eq!(recipients(&alerts.next(start).unwrap()), ["a"]);
+    assert!(alerts.next(start + Duration::from_secs(60)).is_none());
+  }
+}
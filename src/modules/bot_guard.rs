@@ -0,0 +1,322 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use serde_json::json;
+use twitcheventsub::{error, warn, EventSubscription, Subscription, Transport, TwitchEventSubApi};
+
+use crate::modules::{
+  clock::{parse_timestamp, unix_timestamp},
+  helix,
+  sliding_window::SlidingWindow,
+};
+
+#[derive(Serialise, Deserialise, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelProtection {
+  FollowersOnly,
+  EmoteOnly,
+  SlowMode,
+  SubscribersOnly,
+  UniqueChat,
+  ShieldMode,
+}
+
+impl ChannelProtection {
+  // Shield mode isn't one of the chat settings
+  fn chat_setting(&self) -> Option<&'static str> {
+    match self {
+      ChannelProtection::FollowersOnly => Some("follower_mode"),
+      ChannelProtection::EmoteOnly => Some("emote_mode"),
+      ChannelProtection::SlowMode => Some("slow_mode"),
+      ChannelProtection::SubscribersOnly => Some("subscriber_mode"),
+      ChannelProtection::UniqueChat => Some("unique_chat_mode"),
+      ChannelProtection::ShieldMode => None,
+    }
+  }
+
+  pub fn set(&self, twitch: &TwitchEventSubApi, enabled: bool) {
+    let result = match self.chat_setting() {
+      Some(setting) => helix::update_chat_settings(twitch, json!({ setting: enabled })),
+      None => helix::set_shield_mode(twitch, enabled),
+    };
+
+    if let Err(e) = result {
+      error!("Failed to set {:?} to {}: {:?}", self, enabled, e);
+    }
+  }
+
+  pub fn is_enabled(&self, twitch: &TwitchEventSubApi) -> Result<bool, String> {
+    match self.chat_setting() {
+      Some(setting) => helix::get_chat_settings(twitch)
+        .map_err(|e| format!("{:?}", e))?
+        .get(setting)
+        .and_then(|enabled| enabled.as_bool())
+        .ok_or_else(|| format!("{} is missing from the chat settings", setting)),
+      None => helix::get_shield_mode(twitch).map_err(|e| format!("{:?}", e)),
+    }
+  }
+}
+
+// The library has no permissions for these yet, same as raids::raid_permission
+pub fn protection_permissions() -> Vec<Subscription> {
+  [
+    "moderator:manage:chat_settings",
+    "moderator:manage:shield_mode",
+  ]
+  .iter()
+  .map(|scope| {
+    Subscription::Custom((
+      String::new(),
+      scope.to_string(),
+      EventSubscription::new(&Subscription::PermissionIRCRead, Transport::new("")),
+    ))
+  })
+  .collect()
+}
+
+// Protections that were already on before a burst are left alone, so
+// lifting the lockdown puts chat back the way it was
+#[derive(Default)]
+pub struct Lockdown {
+  engaged: Vec<ChannelProtection>,
+}
+
+impl Lockdown {
+  pub fn engage(&mut self, twitch: &TwitchEventSubApi, protections: &[ChannelProtection]) {
+    for protection in protections {
+      if self.engaged.contains(protection) {
+        continue;
+      }
+      match protection.is_enabled(twitch) {
+        Ok(true) => continue,
+        Ok(false) => {}
+        Err(e) => error!("Failed to check {:?}: {}", protection, e),
+      }
+
+      protection.set(twitch, true);
+      self.engaged.push(*protection);
+    }
+  }
+
+  pub fn release(&mut self, twitch: &TwitchEventSubApi) {
+    for protection in self.engaged.drain(..) {
+      protection.set(twitch, false);
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct BotGuardConfig {
+  // More follows than this inside the window counts as a follow bot burst
+  pub max_follows: usize,
+  pub follow_window_seconds: u64,
+  // Raids from accounts younger than this are suspicious
+  pub new_account_days: u64,
+  pub max_new_account_raids: usize,
+  pub raid_window_seconds: u64,
+  pub protections: Vec<ChannelProtection>,
+  // Protections are lifted once there has been no burst activity for this long
+  pub cooldown_seconds: u64,
+}
+
+impl Default for BotGuardConfig {
+  fn default() -> Self {
+    BotGuardConfig {
+      max_follows: 10,
+      follow_window_seconds: 10,
+      new_account_days: 7,
+      max_new_account_raids: 0,
+      raid_window_seconds: 600,
+      protections: vec![ChannelProtection::FollowersOnly],
+      cooldown_seconds: 120,
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GuardEvent {
+  FollowBurst,
+  NewAccountRaid,
+  Released,
+}
+
+pub struct BotGuard {
+  config: BotGuardConfig,
+  follows: SlidingWindow,
+  new_account_raids: SlidingWindow,
+  last_burst: Option<Instant>,
+  lockdown: Lockdown,
+}
+
+impl BotGuard {
+  pub fn new(config: BotGuardConfig) -> BotGuard {
+    BotGuard {
+      follows: SlidingWindow::new(Duration::from_secs(config.follow_window_seconds)),
+      new_account_raids: SlidingWindow::new(Duration::from_secs(config.raid_window_seconds)),
+      config,
+      last_burst: None,
+      lockdown: Lockdown::default(),
+    }
+  }
+
+  pub fn is_engaged(&self) -> bool {
+    self.last_burst.is_some()
+  }
+
+  // Only the first sign of a burst is reported, further activity just
+  // keeps the protections up for longer
+  fn burst(&mut self, event: GuardEvent, now: Instant) -> Option<GuardEvent> {
+    let was_engaged = self.is_engaged();
+    self.last_burst = Some(now);
+    if was_engaged {
+      None
+    } else {
+      Some(event)
+    }
+  }
+
+  pub fn follow(&mut self, now: Instant) -> Option<GuardEvent> {
+    if self.follows.record(now) > self.config.max_follows {
+      self.burst(GuardEvent::FollowBurst, now)
+    } else {
+      None
+    }
+  }
+
+  pub fn raid(&mut self, account_age_days: u64, now: Instant) -> Option<GuardEvent> {
+    if account_age_days >= self.config.new_account_days {
+      return None;
+    }
+
+    if self.new_account_raids.record(now) > self.config.max_new_account_raids {
+      self.burst(GuardEvent::NewAccountRaid, now)
+    } else {
+      None
+    }
+  }
+
+  pub fn tick(&mut self, now: Instant) -> Option<GuardEvent> {
+    let last_burst = self.last_burst?;
+    if now.saturating_duration_since(last_burst).as_secs() >= self.config.cooldown_seconds {
+      self.last_burst = None;
+      Some(GuardEvent::Released)
+    } else {
+      None
+    }
+  }
+
+  pub fn handle(&mut self, twitch: &mut TwitchEventSubApi, event: GuardEvent) {
+    if event == GuardEvent::Released {
+      self.lockdown.release(twitch);
+    } else {
+      self.lockdown.engage(twitch, &self.config.protections);
+    }
+
+    let guard_text = match event {
+      GuardEvent::FollowBurst => "Warning: Channel may be currently being follow botted!",
+      GuardEvent::NewAccountRaid => "Warning: Raided by a brand new account, bots may follow!",
+      GuardEvent::Released => "The bot storm has passed, Owlbot is standing down.",
+    };
+
+    let _ = twitch.send_chat_message(guard_text);
+    println!("{}", guard_text);
+    warn!("{}", guard_text);
+  }
+}
+
+pub fn account_age_days<S: Into<String>>(twitch: &mut TwitchEventSubApi, login: S) -> Option<u64> {
+  let users = twitch.get_users_from_logins(vec![login.into()]).ok()?;
+  let created_at = parse_timestamp(&users.data.first()?.created_at)?;
+  Some(unix_timestamp().saturating_sub(created_at) / 86400)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn guard() -> BotGuard {
+    BotGuard::new(BotGuardConfig {
+      max_follows: 3,
+      follow_window_seconds: 10,
+      new_account_days: 7,
+      max_new_account_raids: 0,
+      raid_window_seconds: 600,
+      protections: Vec::new(),
+      cooldown_seconds: 120,
+    })
+  }
+
+  #[test]
+  fn follows_below_the_limit_are_ignored() {
+    let start = Instant::now();
+    let mut guard = guard();
+
+    for second in 0..3 {
+      assert_eq!(guard.follow(start + Duration::from_secs(second)), None);
+    }
+    assert!(!guard.is_engaged());
+  }
+
+  #[test]
+  fn follow_burst_engages_once() {
+    let start = Instant::now();
+    let mut guard = guard();
+    for _ in 0..3 {
+      guard.follow(start);
+    }
+
+    assert_eq!(guard.follow(start), Some(GuardEvent::FollowBurst));
+    assert_eq!(guard.follow(start), None);
+    assert!(guard.is_engaged());
+  }
+
+  #[test]
+  fn spread_out_follows_are_not_a_burst() {
+    let start = Instant::now();
+    let mut guard = guard();
+
+    for second in 0..10 {
+      assert_eq!(guard.follow(start + Duration::from_secs(second * 5)), None);
+    }
+  }
+
+  #[test]
+  fn new_account_raid_engages() {
+    let start = Instant::now();
+    let mut guard = guard();
+
+    assert_eq!(guard.raid(30, start), None);
+    assert_eq!(guard.raid(1, start), Some(GuardEvent::NewAccountRaid));
+    assert!(guard.is_engaged());
+  }
+
+  #[test]
+  fn releases_after_the_cooldown() {
+    let start = Instant::now();
+    let mut guard = guard();
+    guard.raid(1, start);
+
+    assert_eq!(guard.tick(start + Duration::from_secs(119)), None);
+    assert_eq!(
+      guard.tick(start + Duration::from_secs(120)),
+      Some(GuardEvent::Released)
+    );
+    assert!(!guard.is_engaged());
+    assert_eq!(guard.tick(start + Duration::from_secs(300)), None);
+  }
+
+  #[test]
+  fn burst_activity_extends_the_cooldown() {
+    let start = Instant::now();
+    let mut guard = guard();
+    guard.raid(1, start);
+    guard.raid(1, start + Duration::from_secs(100));
+
+    assert_eq!(guard.tick(start + Duration::from_secs(150)), None);
+    assert_eq!(
+      guard.tick(start + Duration::from_secs(220)),
+      Some(GuardEvent::Released)
+    );
+  }
+}
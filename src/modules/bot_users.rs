@@ -0,0 +1,69 @@
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+
+use crate::modules::consts::AUDIO_ALERT_BOT;
+
+#[derive(Serialise, Deserialise, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BotPolicy {
+  // Messages still count, they just aren't printed
+  HideFromTerminal,
+  DeleteFromChat,
+  // Not ranked, quoted or treated as a first time chatter
+  IgnoreChatter,
+  ForwardToAlerts,
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+pub struct BotUser {
+  pub name: String,
+  pub policies: Vec<BotPolicy>,
+}
+
+impl BotUser {
+  fn new(name: &str, policies: &[BotPolicy]) -> BotUser {
+    BotUser {
+      name: name.to_owned(),
+      policies: policies.to_vec(),
+    }
+  }
+}
+
+// Known bot accounts are trusted, so they skip the spam filters entirely
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct BotUsersConfig {
+  pub users: Vec<BotUser>,
+}
+
+impl Default for BotUsersConfig {
+  fn default() -> Self {
+    BotUsersConfig {
+      users: vec![
+        BotUser::new(AUDIO_ALERT_BOT, &[BotPolicy::DeleteFromChat]),
+        BotUser::new(
+          "Streamlabs",
+          &[BotPolicy::IgnoreChatter, BotPolicy::ForwardToAlerts],
+        ),
+        BotUser::new("Fossabot", &[BotPolicy::IgnoreChatter]),
+        BotUser::new("Nightbot", &[BotPolicy::IgnoreChatter]),
+        BotUser::new("StreamElements", &[BotPolicy::IgnoreChatter]),
+      ],
+    }
+  }
+}
+
+impl BotUsersConfig {
+  pub fn is_bot(&self, username: &str) -> bool {
+    self
+      .users
+      .iter()
+      .any(|bot| bot.name.eq_ignore_ascii_case(username))
+  }
+
+  pub fn has_policy(&self, username: &str, policy: BotPolicy) -> bool {
+    self
+      .users
+      .iter()
+      .any(|bot| bot.name.eq_ignore_ascii_case(username) && bot.policies.contains(&policy))
+  }
+}
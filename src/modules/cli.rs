@@ -0,0 +1,71 @@
+use crate::modules::{
+  config::Config,
+  modlog,
+  raids::{RaidCommand, Raiders},
+  rewards::{self, RewardRegistry, RewardToggle},
+};
+
+pub enum CliCommand {
+  ModLog(usize),
+  ListRewards,
+  SyncRewards,
+  ToggleReward(RewardToggle, String),
+  Raid(RaidCommand),
+}
+
+impl CliCommand {
+  pub fn parse(args: &[String]) -> Option<CliCommand> {
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+      ["modlog"] => Some(CliCommand::ModLog(20)),
+      ["modlog", count] => Some(CliCommand::ModLog(count.parse().unwrap_or(20))),
+      ["rewards", "list"] => Some(CliCommand::ListRewards),
+      ["rewards", "sync"] => Some(CliCommand::SyncRewards),
+      ["rewards", toggle, ref name @ ..] if !name.is_empty() => Some(CliCommand::ToggleReward(
+        RewardToggle::parse(toggle)?,
+        name.join(" "),
+      )),
+      ["raid", ref rest @ ..] => Some(CliCommand::Raid(RaidCommand::parse(rest)?)),
+      _ => None,
+    }
+  }
+
+  pub fn usage() -> &'static str {
+    "Usage: owlbot [modlog [count] | rewards list|sync | rewards pause|resume|enable|disable <name> | raid [channel|cancel]]"
+  }
+
+  pub fn run(self) {
+    match self {
+      CliCommand::ModLog(count) => {
+        let entries = modlog::recent(count);
+        if entries.is_empty() {
+          println!("Owlkatraz is empty, no moderation actions have been logged.");
+        }
+        for entry in entries {
+          println!("{}", entry);
+        }
+      }
+      CliCommand::ListRewards => RewardRegistry::load().list(&mut crate::connect_to_twitch()),
+      CliCommand::SyncRewards => {
+        let mut twitch = crate::connect_to_twitch();
+        let mut registry = RewardRegistry::load();
+        registry.sync(&mut twitch);
+        registry.list(&mut twitch);
+      }
+      CliCommand::ToggleReward(toggle, name) => {
+        match rewards::toggle_reward(
+          &mut crate::connect_to_twitch(),
+          &RewardRegistry::load(),
+          &name,
+          toggle,
+        ) {
+          Ok(text) | Err(text) => println!("{}", text),
+        }
+      }
+      CliCommand::Raid(command) => command.run(
+        &mut crate::connect_to_twitch(),
+        &Config::load().raids,
+        &Raiders::load(),
+      ),
+    }
+  }
+}
@@ -0,0 +1,71 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn unix_timestamp() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+// Days since the unix epoch to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  (year, month, day)
+}
+
+pub fn format_timestamp(timestamp: u64) -> String {
+  let (year, month, day) = civil_from_days((timestamp / 86400) as i64);
+  let seconds_today = timestamp % 86400;
+  format!(
+    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+    year,
+    month,
+    day,
+    seconds_today / 3600,
+    seconds_today / 60 % 60,
+    seconds_today % 60
+  )
+}
+
+// (year, month, day) civil date to days since the unix epoch
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let yoe = year.rem_euclid(400);
+  let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+  let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+  let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+  era * 146097 + doe - 719468
+}
+
+// Parses the RFC3339 timestamps twitch hands out, e.g. 2016-12-14T20:32:28Z
+pub fn parse_timestamp(timestamp: &str) -> Option<u64> {
+  let (date, time) = timestamp.split_once('T')?;
+  let date = date
+    .split('-')
+    .map(|part| part.parse::<u32>().ok())
+    .collect::<Option<Vec<_>>>()?;
+  let time = time
+    .trim_end_matches('Z')
+    .split(':')
+    .map(|part| part.split('.').next()?.parse::<u64>().ok())
+    .collect::<Option<Vec<_>>>()?;
+
+  match (&date[..], &time[..]) {
+    ([year, month, day], [hours, minutes, seconds]) => {
+      let days = days_from_civil(*year as i64, *month, *day);
+      Some(days as u64 * 86400 + hours * 3600 + minutes * 60 + seconds)
+    }
+    _ => None,
+  }
+}
@@ -0,0 +1,41 @@
+use std::fs;
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+
+use crate::modules::{
+  ads::AdConfig, alerts::AlertConfig, bot_guard::BotGuardConfig, bot_users::BotUsersConfig,
+  consts::CONFIG_FILE, filters::FilterConfig, flood::FloodConfig, followers::FollowConfig,
+  moderation::ModerationConfig, overlay::OverlayConfig, raids::RaidConfig,
+  redemptions::RedemptionConfig, thanks::ThanksConfig, tts::TtsConfig,
+};
+
+// Every section falls back to its defaults so the config file only
+// needs to contain the values that are being changed.
+#[derive(Serialise, Deserialise, Default, Clone)]
+#[serde(default)]
+pub struct Config {
+  pub moderation: ModerationConfig,
+  pub bot_guard: BotGuardConfig,
+  pub flood: FloodConfig,
+  pub filters: FilterConfig,
+  pub bot_users: BotUsersConfig,
+  pub tts: TtsConfig,
+  pub redemptions: RedemptionConfig,
+  pub overlay: OverlayConfig,
+  pub alerts: AlertConfig,
+  pub thanks: ThanksConfig,
+  pub raids: RaidConfig,
+  pub ads: AdConfig,
+  pub followers: FollowConfig,
+}
+
+impl Config {
+  pub fn load() -> Config {
+    let mut config = Config::default();
+    if let Ok(config_string) = fs::read_to_string(CONFIG_FILE) {
+      config = serde_json::from_str(&config_string).unwrap();
+    }
+
+    config
+  }
+}
@@ -26,3 +26,24 @@ pub const SPEECH_FILE: &str = "speech";
 pub const COUNTERS_FILE: &str = "counters";
 
 pub const THE_GREAT_FIMSH: &str = "TheGreatFimsh";
+pub const CONFIG_FILE: &str = "config";
+pub const STRIKES_FILE: &str = "strikes";
+pub const FILTERS_FILE: &str = "chat_filters";
+pub const VIEWERS_FILE: &str = "viewers";
+pub const REWARDS_FILE: &str = "rewards";
+pub const RAIDERS_FILE: &str = "raiders";
+
+pub const BAN_URL: &str = "https://api.twitch.tv/helix/moderation/bans";
+pub const MODLOG_FILE: &str = "modlog.jsonl";
+pub const CHAT_SETTINGS_URL: &str = "https://api.twitch.tv/helix/chat/settings";
+pub const SHIELD_MODE_URL: &str = "https://api.twitch.tv/helix/moderation/shield_mode";
+pub const REDEMPTIONS_URL: &str =
+  "https://api.twitch.tv/helix/channel_points/custom_rewards/redemptions";
+pub const CHANNELS_URL: &str = "https://api.twitch.tv/helix/channels";
+pub const SHOUTOUTS_URL: &str = "https://api.twitch.tv/helix/chat/shoutouts";
+pub const RAIDS_URL: &str = "https://api.twitch.tv/helix/raids";
+pub const STREAMS_URL: &str = "https://api.twitch.tv/helix/streams";
+pub const FOLLOWERS_URL: &str = "https://api.twitch.tv/helix/channels/followers";
+pub const CHEERMOTES_URL: &str = "https://api.twitch.tv/helix/bits/cheermotes";
+pub const POLLS_URL: &str = "https://api.twitch.tv/helix/polls";
+pub const PREDICTIONS_URL: &str = "https://api.twitch.tv/helix/predictions";
@@ -0,0 +1,165 @@
+use std::time::{Duration, Instant};
+
+// Warns in the terminal when an effect is about to run out
+const ENDING_SOON: Duration = Duration::from_secs(60);
+
+pub struct ActiveEffect {
+  pub name: String,
+  pub redeemed_by: String,
+  expires: Instant,
+  warned: bool,
+}
+
+fn format_remaining(remaining: Duration) -> String {
+  let seconds = remaining.as_secs();
+  if seconds >= 60 {
+    format!("{}m {}s", seconds / 60, seconds % 60)
+  } else {
+    format!("{}s", seconds)
+  }
+}
+
+// Redeems that last for a while, callers pass in the current instant so
+// the clock can be faked.
+#[derive(Default)]
+pub struct Effects {
+  active: Vec<ActiveEffect>,
+}
+
+impl Effects {
+  // Redeeming an effect that is already running adds to its time
+  pub fn start<S: Into<String>, T: Into<String>>(
+    &mut self,
+    name: S,
+    redeemed_by: T,
+    duration: Duration,
+    now: Instant,
+  ) {
+    let name = name.into();
+    let redeemed_by = redeemed_by.into();
+    match self.active.iter_mut().find(|effect| effect.name == name) {
+      Some(effect) => {
+        effect.expires += duration;
+        effect.warned = false;
+        println!(
+          "Effect: {} extended by {} ({} left)",
+          name,
+          redeemed_by,
+          format_remaining(effect.expires.saturating_duration_since(now))
+        );
+      }
+      None => {
+        println!(
+          "Effect: {} started by {} for {}",
+          name,
+          redeemed_by,
+          format_remaining(duration)
+        );
+        self.active.push(ActiveEffect {
+          name,
+          redeemed_by,
+          expires: now + duration,
+          warned: false,
+        });
+      }
+    }
+  }
+
+  pub fn expire(&mut self, now: Instant) -> Vec<ActiveEffect> {
+    for effect in &mut self.active {
+      if !effect.warned && effect.expires.saturating_duration_since(now) <= ENDING_SOON {
+        effect.warned = true;
+        println!(
+          "Effect: {} ends in {}",
+          effect.name,
+          format_remaining(effect.expires.saturating_duration_since(now))
+        );
+      }
+    }
+
+    let (expired, active) = self
+      .active
+      .drain(..)
+      .partition(|effect| effect.expires <= now);
+    self.active = active;
+    expired
+  }
+
+  pub fn summary(&self, now: Instant) -> String {
+    if self.active.is_empty() {
+      return "No effects are active.".to_owned();
+    }
+
+    self
+      .active
+      .iter()
+      .map(|effect| {
+        format!(
+          "{} ({} left)",
+          effect.name,
+          format_remaining(effect.expires.saturating_duration_since(now))
+        )
+      })
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+
+  pub fn print(&self, now: Instant) {
+    if self.active.is_empty() {
+      println!("No effects are active.");
+    }
+    for effect in &self.active {
+      println!(
+        "Effect: {} from {}, {} left",
+        effect.name,
+        effect.redeemed_by,
+        format_remaining(effect.expires.saturating_duration_since(now))
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const HALF_HOUR: Duration = Duration::from_secs(30 * 60);
+
+  #[test]
+  fn effects_expire_once_their_time_is_up() {
+    let start = Instant::now();
+    let mut effects = Effects::default();
+    effects.start("EU mode", "owl", HALF_HOUR, start);
+
+    assert!(effects
+      .expire(start + HALF_HOUR - Duration::from_secs(1))
+      .is_empty());
+    let expired = effects.expire(start + HALF_HOUR);
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].name, "EU mode");
+    assert!(effects.expire(start + HALF_HOUR * 2).is_empty());
+  }
+
+  #[test]
+  fn redeeming_again_extends_the_effect() {
+    let start = Instant::now();
+    let mut effects = Effects::default();
+    effects.start("EU mode", "owl", HALF_HOUR, start);
+    effects.start("EU mode", "kal", HALF_HOUR, start + Duration::from_secs(60));
+
+    assert!(effects.expire(start + HALF_HOUR).is_empty());
+    assert_eq!(effects.expire(start + HALF_HOUR * 2).len(), 1);
+  }
+
+  #[test]
+  fn summary_lists_time_left() {
+    let start = Instant::now();
+    let mut effects = Effects::default();
+    effects.start("EU mode", "owl", Duration::from_secs(90), start);
+
+    assert_eq!(
+      effects.summary(start + Duration::from_secs(30)),
+      "EU mode (1m 0s left)"
+    );
+  }
+}
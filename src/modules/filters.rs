@@ -0,0 +1,190 @@
+use std::fs;
+use std::io::Write;
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::Badge;
+
+use crate::modules::consts::{FILTERS_FILE, STREAM_ACCOUNT};
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct FilterConfig {
+  // Blocked terms and links are filtered for every chatter, except these
+  pub exempt_subscribers: bool,
+  pub exempt_vips: bool,
+  pub exempt_moderators: bool,
+  // When false, links are only filtered for new chatters. Anything with a
+  // dot in it counts as a link, so this also catches file names like main.rs
+  pub filter_links_for_everyone: bool,
+}
+
+impl Default for FilterConfig {
+  fn default() -> Self {
+    FilterConfig {
+      exempt_subscribers: true,
+      exempt_vips: true,
+      exempt_moderators: true,
+      filter_links_for_everyone: false,
+    }
+  }
+}
+
+impl FilterConfig {
+  pub fn is_exempt(&self, username: &str, badges: &[Badge]) -> bool {
+    username.eq_ignore_ascii_case(STREAM_ACCOUNT)
+      || badges.iter().any(|badge| match badge.set_id.as_str() {
+        "broadcaster" => true,
+        "subscriber" | "founder" => self.exempt_subscribers,
+        "vip" => self.exempt_vips,
+        "moderator" => self.exempt_moderators,
+        _ => false,
+      })
+  }
+}
+
+// Lists moderators manage from chat with !block, !unblock and !allowlink
+#[derive(Serialise, Deserialise)]
+#[serde(default)]
+pub struct ChatFilters {
+  pub blocked_terms: Vec<String>,
+  // A domain allows itself and its subdomains, an entry with a path
+  // (discord.gg/abc) only allows links starting with it
+  pub allowed_domains: Vec<String>,
+  // Only count towards the spam score of new chatters
+  pub sus_words: Vec<String>,
+}
+
+impl Default for ChatFilters {
+  fn default() -> Self {
+    ChatFilters {
+      blocked_terms: Vec::new(),
+      allowed_domains: vec![
+        "github.com".to_owned(),
+        "twitch.tv".to_owned(),
+        "discord.gg/8pdfBzGbgB".to_owned(),
+      ],
+      sus_words: [
+        "cheap",
+        "view",
+        "streamrise",
+        "onlyfans",
+        "http",
+        "promot",
+        "activat",
+        "free",
+        "hosthub",
+      ]
+      .iter()
+      .map(|word| word.to_string())
+      .collect(),
+    }
+  }
+}
+
+impl ChatFilters {
+  pub fn load() -> ChatFilters {
+    let mut filters = ChatFilters::default();
+    if let Ok(filters_string) = fs::read_to_string(FILTERS_FILE) {
+      filters = serde_json::from_str(&filters_string).unwrap();
+    }
+
+    filters
+  }
+
+  pub fn save(&self) {
+    let mut file = fs::File::create(FILTERS_FILE).unwrap();
+    if let Ok(filters) = serde_json::to_string_pretty(self) {
+      file.write_all(filters.as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+  }
+
+  pub fn block<S: Into<String>>(&mut self, term: S) -> bool {
+    let term = term.into().to_lowercase();
+    if term.is_empty() || self.blocked_terms.contains(&term) {
+      return false;
+    }
+    self.blocked_terms.push(term);
+    self.save();
+    true
+  }
+
+  pub fn unblock<S: Into<String>>(&mut self, term: S) -> bool {
+    let term = term.into().to_lowercase();
+    let count = self.blocked_terms.len();
+    self.blocked_terms.retain(|blocked| *blocked != term);
+    self.save();
+    count != self.blocked_terms.len()
+  }
+
+  pub fn allow_link<S: Into<String>>(&mut self, domain: S) -> bool {
+    let domain = strip_scheme(&domain.into().to_lowercase()).to_owned();
+    if domain.is_empty()
+      || self
+        .allowed_domains
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(&domain))
+    {
+      return false;
+    }
+    self.allowed_domains.push(domain);
+    self.save();
+    true
+  }
+
+  pub fn blocked_terms_in(&self, lower_message: &str) -> Vec<&String> {
+    self
+      .blocked_terms
+      .iter()
+      .filter(|term| lower_message.contains(term.as_str()))
+      .collect()
+  }
+
+  pub fn is_allowed_link(&self, link: &str) -> bool {
+    let host = link.split('/').next().unwrap_or(link);
+    self.allowed_domains.iter().any(|allowed| {
+      let allowed = allowed.to_lowercase();
+      if allowed.contains('/') {
+        link.starts_with(&allowed)
+      } else {
+        host == allowed || host.ends_with(&format!(".{}", allowed))
+      }
+    })
+  }
+
+  // Drops allowed links, or every link when the chatter has a permit
+  pub fn without_links(&self, lower_message: &str, permitted: bool) -> String {
+    lower_message
+      .split_whitespace()
+      .filter(|word| {
+        let word = strip_scheme(word);
+        !(self.is_allowed_link(word) || (permitted && looks_like_link(word)))
+      })
+      .collect::<Vec<_>>()
+      .join(" ")
+  }
+
+  // Every word that looks like it contains a domain and isn't allowed
+  pub fn disallowed_links<'a>(&self, lower_message: &'a str) -> Vec<&'a str> {
+    lower_message
+      .split_whitespace()
+      .map(strip_scheme)
+      .filter(|word| looks_like_link(word))
+      .filter(|link| !self.is_allowed_link(link))
+      .collect()
+  }
+}
+
+pub fn looks_like_link(word: &str) -> bool {
+  word
+    .split('.')
+    .skip(1)
+    .any(|s| s.len() > 1 && s.chars().take(2).all(char::is_alphabetic))
+}
+
+fn strip_scheme(word: &str) -> &str {
+  let word = word
+    .trim_start_matches("https://")
+    .trim_start_matches("http://");
+  word.trim_start_matches("www.")
+}
@@ -0,0 +1,288 @@
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::{warn, TwitchEventSubApi};
+
+use crate::modules::{
+  bot_guard::{ChannelProtection, Lockdown},
+  moderation::FlaggedMessage,
+};
+
+const FLOOD_RULE: &str = "new chatter flood";
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct FloodConfig {
+  // This many different new chatters posting similar messages inside the
+  // window counts as a flood
+  pub min_chatters: usize,
+  pub window_seconds: u64,
+  // Fraction of shared words (0.0 - 1.0) for two messages to count as similar
+  pub similarity: f32,
+  pub protections: Vec<ChannelProtection>,
+  pub cooldown_seconds: u64,
+  // Raiders pasting the raid message aren't a flood, so for this long after
+  // a raid from an established account it takes raid_min_chatters instead
+  pub raid_grace_seconds: u64,
+  pub raid_min_chatters: usize,
+}
+
+impl Default for FloodConfig {
+  fn default() -> Self {
+    FloodConfig {
+      min_chatters: 5,
+      window_seconds: 30,
+      similarity: 0.6,
+      protections: vec![ChannelProtection::SlowMode, ChannelProtection::UniqueChat],
+      cooldown_seconds: 300,
+      raid_grace_seconds: 120,
+      raid_min_chatters: 25,
+    }
+  }
+}
+
+pub enum FloodEvent {
+  Detected(Vec<FlaggedMessage>),
+  // More flood messages after the guard has already been engaged
+  Matched(Vec<FlaggedMessage>),
+  Released,
+}
+
+impl FloodEvent {
+  pub fn into_messages(self) -> Vec<FlaggedMessage> {
+    match self {
+      FloodEvent::Detected(messages) | FloodEvent::Matched(messages) => messages,
+      FloodEvent::Released => Vec::new(),
+    }
+  }
+}
+
+struct RecentMessage {
+  flagged: FlaggedMessage,
+  words: HashSet<String>,
+  received: Instant,
+}
+
+fn words(text: &str) -> HashSet<String> {
+  text
+    .split_whitespace()
+    .map(|word| {
+      word
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+    })
+    .filter(|word| !word.is_empty())
+    .collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+  if a.is_empty() && b.is_empty() {
+    return 0.0;
+  }
+  a.intersection(b).count() as f32 / a.union(b).count() as f32
+}
+
+// Watches new chatters for hate raids, callers pass in the current
+// instant so the clock can be faked.
+pub struct FloodGuard {
+  config: FloodConfig,
+  recent: VecDeque<RecentMessage>,
+  // Words of the message that set off the flood, later messages similar
+  // to it are removed until the guard is released
+  flood_words: Option<HashSet<String>>,
+  last_flood: Instant,
+  last_raid: Option<Instant>,
+  lockdown: Lockdown,
+}
+
+impl FloodGuard {
+  pub fn new(config: FloodConfig) -> FloodGuard {
+    FloodGuard {
+      config,
+      recent: VecDeque::new(),
+      flood_words: None,
+      last_flood: Instant::now(),
+      last_raid: None,
+      lockdown: Lockdown::default(),
+    }
+  }
+
+  pub fn raided(&mut self, now: Instant) {
+    self.last_raid = Some(now);
+  }
+
+  pub fn is_engaged(&self) -> bool {
+    self.flood_words.is_some()
+  }
+
+  pub fn record(&mut self, flagged: &FlaggedMessage, now: Instant) -> Option<FloodEvent> {
+    let min_chatters = if self.last_raid.is_some_and(|raided| {
+      now.saturating_duration_since(raided).as_secs() < self.config.raid_grace_seconds
+    }) {
+      self.config.raid_min_chatters.max(self.config.min_chatters)
+    } else {
+      self.config.min_chatters
+    };
+
+    let window = Duration::from_secs(self.config.window_seconds);
+    while let Some(oldest) = self.recent.front() {
+      if now.saturating_duration_since(oldest.received) < window {
+        break;
+      }
+      self.recent.pop_front();
+    }
+
+    let mut flagged = flagged.clone();
+    flagged.spam_check.matched_rules.push(FLOOD_RULE.to_owned());
+    let message_words = words(&flagged.text);
+
+    if let Some(flood_words) = &self.flood_words {
+      if similarity(flood_words, &message_words) >= self.config.similarity {
+        self.last_flood = now;
+        return Some(FloodEvent::Matched(vec![flagged]));
+      }
+    }
+
+    let similar = self
+      .recent
+      .iter()
+      .filter(|recent| similarity(&recent.words, &message_words) >= self.config.similarity)
+      .collect::<Vec<_>>();
+    let chatters = similar
+      .iter()
+      .map(|recent| &recent.flagged.username)
+      .chain([&flagged.username])
+      .collect::<HashSet<_>>();
+
+    if chatters.len() >= min_chatters {
+      let mut flooded = similar
+        .iter()
+        .map(|recent| recent.flagged.clone())
+        .collect::<Vec<_>>();
+      let flooded_ids = flooded
+        .iter()
+        .map(|flooded| flooded.message_id.to_owned())
+        .collect::<HashSet<_>>();
+      self
+        .recent
+        .retain(|recent| !flooded_ids.contains(&recent.flagged.message_id));
+      flooded.push(flagged);
+
+      let was_engaged = self.is_engaged();
+      self.flood_words = Some(message_words);
+      self.last_flood = now;
+      return if was_engaged {
+        Some(FloodEvent::Matched(flooded))
+      } else {
+        Some(FloodEvent::Detected(flooded))
+      };
+    }
+
+    self.recent.push_back(RecentMessage {
+      flagged,
+      words: message_words,
+      received: now,
+    });
+    None
+  }
+
+  pub fn tick(&mut self, now: Instant) -> Option<FloodEvent> {
+    if self.is_engaged()
+      && now.saturating_duration_since(self.last_flood).as_secs() >= self.config.cooldown_seconds
+    {
+      self.flood_words = None;
+      Some(FloodEvent::Released)
+    } else {
+      None
+    }
+  }
+
+  pub fn handle(&mut self, twitch: &mut TwitchEventSubApi, event: &FloodEvent) {
+    let flood_text = match event {
+      FloodEvent::Detected(messages) => format!(
+        "Moderators: new chatters are flooding chat, Owlbot is removing {} messages and locking things down!",
+        messages.len()
+      ),
+      FloodEvent::Released => "The flood has receded, chat restrictions are lifted.".to_owned(),
+      FloodEvent::Matched(_) => return,
+    };
+
+    let enabled = !matches!(event, FloodEvent::Released);
+    if enabled {
+      self.lockdown.engage(twitch, &self.config.protections);
+    } else {
+      self.lockdown.release(twitch);
+    }
+
+    if enabled {
+      let _ = twitch.send_announcement(flood_text.to_owned(), Some("orange"));
+    } else {
+      let _ = twitch.send_chat_message(flood_text.to_owned());
+    }
+    println!("{}", flood_text);
+    warn!("{}", flood_text);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modules::moderation::SpamCheck;
+
+  fn message(username: &str, text: &str) -> FlaggedMessage {
+    FlaggedMessage {
+      username: username.to_owned(),
+      user_id: username.to_owned(),
+      message_id: format!("{}-{}", username, text.len()),
+      text: text.to_owned(),
+      spam_check: SpamCheck {
+        score: 0,
+        matched_rules: Vec::new(),
+        has_blocked_term: false,
+        has_link: false,
+      },
+    }
+  }
+
+  fn flood(guard: &mut FloodGuard, chatters: usize, now: Instant) -> Option<FloodEvent> {
+    (0..chatters)
+      .filter_map(|user| {
+        guard.record(
+          &message(&format!("chatter{}", user), "owlRaid OWL RAID owlRaid"),
+          now,
+        )
+      })
+      .last()
+  }
+
+  #[test]
+  fn similar_messages_from_new_chatters_flood() {
+    let mut guard = FloodGuard::new(FloodConfig::default());
+
+    let event = flood(&mut guard, 5, Instant::now());
+    assert!(matches!(event, Some(FloodEvent::Detected(messages)) if messages.len() == 5));
+    assert!(guard.is_engaged());
+  }
+
+  #[test]
+  fn raid_messages_need_more_chatters_to_flood() {
+    let start = Instant::now();
+    let mut guard = FloodGuard::new(FloodConfig::default());
+    guard.raided(start);
+
+    assert!(flood(&mut guard, 10, start + Duration::from_secs(10)).is_none());
+    assert!(flood(&mut guard, 25, start + Duration::from_secs(60)).is_some());
+  }
+
+  #[test]
+  fn raid_grace_runs_out() {
+    let start = Instant::now();
+    let mut guard = FloodGuard::new(FloodConfig::default());
+    guard.raided(start);
+
+    assert!(flood(&mut guard, 5, start + Duration::from_secs(120)).is_some());
+  }
+}
@@ -0,0 +1,183 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::TwitchEventSubApi;
+
+use crate::modules::{clock, templates};
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct FollowConfig {
+  pub welcome: bool,
+  // Follows within this long of the first are welcomed together
+  pub batch_seconds: u64,
+  // {names} is everyone in the batch, e.g. "X, Y and Z"
+  pub welcome_message: String,
+}
+
+impl Default for FollowConfig {
+  fn default() -> Self {
+    FollowConfig {
+      welcome: true,
+      batch_seconds: 20,
+      welcome_message: "Welcome to the parliament {names}!".to_owned(),
+    }
+  }
+}
+
+fn join_names(names: &[String]) -> String {
+  match names {
+    [] => String::new(),
+    [name] => name.to_owned(),
+    [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+  }
+}
+
+// Callers pass in the current instant so the clock can be faked
+pub struct FollowWelcomes {
+  config: FollowConfig,
+  pending: Vec<String>,
+  first_at: Option<Instant>,
+}
+
+impl FollowWelcomes {
+  pub fn new(config: FollowConfig) -> FollowWelcomes {
+    FollowWelcomes {
+      config,
+      pending: Vec::new(),
+      first_at: None,
+    }
+  }
+
+  pub fn push(&mut self, username: &str, now: Instant) {
+    if !self.config.welcome || self.pending.iter().any(|name| name == username) {
+      return;
+    }
+
+    self.first_at.get_or_insert(now);
+    self.pending.push(username.to_owned());
+  }
+
+  pub fn tick(&mut self, twitch: &mut TwitchEventSubApi, suppressed: bool, now: Instant) {
+    if let Some(welcome) = self.welcome(suppressed, now) {
+      let _ = twitch.send_chat_message(welcome);
+    }
+  }
+
+  // Follows that arrive during a follow-bot burst are most likely the bots,
+  // so they are dropped rather than welcomed
+  fn welcome(&mut self, suppressed: bool, now: Instant) -> Option<String> {
+    let first_at = self.first_at?;
+    if !suppressed && now.duration_since(first_at) < Duration::from_secs(self.config.batch_seconds)
+    {
+      return None;
+    }
+
+    self.first_at = None;
+    let names = std::mem::take(&mut self.pending);
+    if suppressed {
+      println!(
+        "Not welcoming {} followers during a follow-bot burst",
+        names.len()
+      );
+      return None;
+    }
+
+    Some(templates::render(
+      &self.config.welcome_message,
+      &[("names", &join_names(&names))],
+    ))
+  }
+}
+
+pub fn followage_text(username: &str, followed_at: u64, now: u64) -> String {
+  let days = now.saturating_sub(followed_at) / 86400;
+  let (years, months, days) = (days / 365, days % 365 / 30, days % 365 % 30);
+
+  let mut parts = Vec::new();
+  for (amount, unit) in [(years, "year"), (months, "month"), (days, "day")] {
+    if amount > 0 {
+      parts.push(format!(
+        "{} {}{}",
+        amount,
+        unit,
+        if amount == 1 { "" } else { "s" }
+      ));
+    }
+  }
+
+  let (date, _) = clock::format_timestamp(followed_at)
+    .split_once(' ')
+    .map(|(date, time)| (date.to_owned(), time.to_owned()))
+    .unwrap_or_default();
+  if parts.is_empty() {
+    format!("{} followed today ({}), welcome!", username, date)
+  } else {
+    format!(
+      "{} has been following for {} (since {})!",
+      username,
+      join_names(&parts),
+      date
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn follows_are_welcomed_together() {
+    let start = Instant::now();
+    let mut welcomes = FollowWelcomes::new(FollowConfig::default());
+    welcomes.push("owl", start);
+    welcomes.push("kal", start + Duration::from_secs(5));
+    welcomes.push("owl", start + Duration::from_secs(6));
+    welcomes.push("tyto", start + Duration::from_secs(10));
+
+    assert_eq!(
+      welcomes.welcome(false, start + Duration::from_secs(19)),
+      None
+    );
+    assert_eq!(
+      welcomes
+        .welcome(false, start + Duration::from_secs(20))
+        .as_deref(),
+      Some("Welcome to the parliament owl, kal and tyto!")
+    );
+    assert_eq!(
+      welcomes.welcome(false, start + Duration::from_secs(40)),
+      None
+    );
+  }
+
+  #[test]
+  fn follows_during_a_burst_are_dropped() {
+    let start = Instant::now();
+    let mut welcomes = FollowWelcomes::new(FollowConfig::default());
+    welcomes.push("bot1", start);
+    welcomes.push("bot2", start);
+
+    assert_eq!(welcomes.welcome(true, start), None);
+
+    welcomes.push("owl", start + Duration::from_secs(30));
+    assert_eq!(
+      welcomes
+        .welcome(false, start + Duration::from_secs(50))
+        .as_deref(),
+      Some("Welcome to the parliament owl!")
+    );
+  }
+
+  #[test]
+  fn followage_lists_years_months_and_days() {
+    let followed_at = 1_700_000_000;
+    let now = followed_at + (365 + 30 * 2 + 1) * 86400;
+
+    assert!(followage_text("owl", followed_at, now)
+      .starts_with("owl has been following for 1 year, 2 months and 1 day (since "));
+    assert!(
+      followage_text("owl", followed_at, followed_at + 60).starts_with("owl followed today (")
+    );
+  }
+}
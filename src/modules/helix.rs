@@ -0,0 +1,522 @@
+use serde::Deserialize as Deserialise;
+use serde_json::json;
+use twitcheventsub::{EventSubError, TwitchEventSubApi, TwitchHttpRequest};
+
+use crate::modules::consts::*;
+
+// Endpoints that TwitchEventSubApi doesn't wrap yet, the bot acts as
+// its own moderator the same way delete_message does.
+struct HelixAuth {
+  access_token: String,
+  client_id: String,
+  broadcaster_id: String,
+}
+
+impl HelixAuth {
+  fn from(twitch: &TwitchEventSubApi) -> HelixAuth {
+    let keys = twitch.get_twitch_keys();
+    HelixAuth {
+      access_token: keys.access_token.expect("No Access Token set").get_token(),
+      client_id: keys.client_id,
+      broadcaster_id: keys.broadcaster_account_id,
+    }
+  }
+}
+
+pub fn ban_user<S: Into<String>, T: Into<String>>(
+  twitch: &TwitchEventSubApi,
+  user_id: S,
+  reason: T,
+) -> Result<String, EventSubError> {
+  ban(twitch, user_id.into(), None, reason.into())
+}
+
+// TwitchEventSubApi::timeout_user doesn't report whether it worked
+pub fn timeout_user<S: Into<String>, T: Into<String>>(
+  twitch: &TwitchEventSubApi,
+  user_id: S,
+  seconds: u32,
+  reason: T,
+) -> Result<String, EventSubError> {
+  ban(twitch, user_id.into(), Some(seconds), reason.into())
+}
+
+fn ban(
+  twitch: &TwitchEventSubApi,
+  user_id: String,
+  seconds: Option<u32>,
+  reason: String,
+) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?broadcaster_id={}&moderator_id={}",
+    BAN_URL, auth.broadcaster_id, auth.broadcaster_id
+  );
+  let mut data = json!({
+    "data": {
+      "user_id": user_id,
+      "reason": reason,
+    }
+  });
+  if let Some(seconds) = seconds {
+    data["data"]["duration"] = json!(seconds);
+  }
+
+  TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .json_content()
+    .is_post(data.to_string())
+    .run()
+}
+
+// Only the settings present in `settings` are changed, e.g. {"follower_mode": true}
+pub fn update_chat_settings(
+  twitch: &TwitchEventSubApi,
+  settings: serde_json::Value,
+) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?broadcaster_id={}&moderator_id={}",
+    CHAT_SETTINGS_URL, auth.broadcaster_id, auth.broadcaster_id
+  );
+
+  TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .json_content()
+    .is_patch(settings.to_string())
+    .run()
+}
+
+#[derive(Deserialise)]
+struct ChatSettingsResponse {
+  data: Vec<serde_json::Value>,
+}
+
+// The settings object as twitch returns it, e.g. {"follower_mode": false, ..}
+pub fn get_chat_settings(twitch: &TwitchEventSubApi) -> Result<serde_json::Value, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?broadcaster_id={}&moderator_id={}",
+    CHAT_SETTINGS_URL, auth.broadcaster_id, auth.broadcaster_id
+  );
+
+  let response = TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .run()?;
+  let settings: ChatSettingsResponse =
+    serde_json::from_str(&response).map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+  settings
+    .data
+    .into_iter()
+    .next()
+    .ok_or_else(|| EventSubError::HttpFailed("No chat settings returned".to_owned()))
+}
+
+#[derive(Deserialise)]
+struct ShieldModeStatus {
+  is_active: bool,
+}
+
+#[derive(Deserialise)]
+struct ShieldModeResponse {
+  data: Vec<ShieldModeStatus>,
+}
+
+pub fn get_shield_mode(twitch: &TwitchEventSubApi) -> Result<bool, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?broadcaster_id={}&moderator_id={}",
+    SHIELD_MODE_URL, auth.broadcaster_id, auth.broadcaster_id
+  );
+
+  let response = TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .run()?;
+  let shield_mode: ShieldModeResponse =
+    serde_json::from_str(&response).map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+  Ok(
+    shield_mode
+      .data
+      .first()
+      .is_some_and(|status| status.is_active),
+  )
+}
+
+// TwitchHttpRequest can't send PUTs, so this one goes through attohttpc
+pub fn set_shield_mode(twitch: &TwitchEventSubApi, active: bool) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?broadcaster_id={}&moderator_id={}",
+    SHIELD_MODE_URL, auth.broadcaster_id, auth.broadcaster_id
+  );
+
+  let response = attohttpc::put(url)
+    .bearer_auth(auth.access_token)
+    .header("Client-Id", auth.client_id)
+    .header("Content-Type", "application/json")
+    .text(json!({ "is_active": active }).to_string())
+    .send()
+    .map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+
+  let is_success = response.is_success();
+  let body = response
+    .text()
+    .map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+  if is_success {
+    Ok(body)
+  } else {
+    Err(EventSubError::HttpFailed(body))
+  }
+}
+
+// Identifies a channel point redemption so it can be fulfilled or refunded
+#[derive(Clone, Debug)]
+pub struct Redemption {
+  pub reward_id: String,
+  pub id: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RedemptionStatus {
+  Fulfilled,
+  // Refunds the channel points
+  Canceled,
+}
+
+// Only works for rewards created with the bot's client id
+pub fn update_redemption_status(
+  twitch: &TwitchEventSubApi,
+  redemption: &Redemption,
+  status: RedemptionStatus,
+) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?broadcaster_id={}&reward_id={}&id={}",
+    REDEMPTIONS_URL, auth.broadcaster_id, redemption.reward_id, redemption.id
+  );
+  let status = match status {
+    RedemptionStatus::Fulfilled => "FULFILLED",
+    RedemptionStatus::Canceled => "CANCELED",
+  };
+
+  TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .json_content()
+    .is_patch(json!({ "status": status }).to_string())
+    .run()
+}
+
+#[derive(Deserialise, Clone, Debug)]
+pub struct ChannelInformation {
+  // Empty when the channel has never set a category
+  pub game_name: String,
+  pub title: String,
+}
+
+#[derive(Deserialise)]
+struct ChannelInformationResponse {
+  data: Vec<ChannelInformation>,
+}
+
+pub fn get_channel_information<S: Into<String>>(
+  twitch: &TwitchEventSubApi,
+  broadcaster_id: S,
+) -> Result<Option<ChannelInformation>, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!("{}?broadcaster_id={}", CHANNELS_URL, broadcaster_id.into());
+
+  let response = TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .run()?;
+  let channels: ChannelInformationResponse =
+    serde_json::from_str(&response).map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+  Ok(channels.data.into_iter().next())
+}
+
+// TwitchEventSubApi::send_shoutout drops the response, this one returns it
+// so a shoutout that hit a cooldown can be tried again
+pub fn send_shoutout<S: Into<String>>(
+  twitch: &TwitchEventSubApi,
+  to_broadcaster_id: S,
+) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?from_broadcaster_id={}&to_broadcaster_id={}&moderator_id={}",
+    SHOUTOUTS_URL,
+    auth.broadcaster_id,
+    to_broadcaster_id.into(),
+    auth.broadcaster_id
+  );
+
+  TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .json_content()
+    .is_post("")
+    .run()
+}
+
+// Twitch shows a 90 second countdown before the raid goes through
+pub fn start_raid<S: Into<String>>(
+  twitch: &TwitchEventSubApi,
+  to_broadcaster_id: S,
+) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?from_broadcaster_id={}&to_broadcaster_id={}",
+    RAIDS_URL,
+    auth.broadcaster_id,
+    to_broadcaster_id.into()
+  );
+
+  TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .json_content()
+    .is_post("")
+    .run()
+}
+
+pub fn cancel_raid(twitch: &TwitchEventSubApi) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!("{}?broadcaster_id={}", RAIDS_URL, auth.broadcaster_id);
+
+  TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .is_delete()
+    .run()
+}
+
+#[derive(Deserialise, Clone, Debug)]
+pub struct LiveStream {
+  pub user_login: String,
+  pub game_name: String,
+  pub viewer_count: u32,
+}
+
+#[derive(Deserialise)]
+struct LiveStreamsResponse {
+  data: Vec<LiveStream>,
+}
+
+// Channels that aren't live are left out, up to 100 logins at a time
+pub fn get_live_streams(
+  twitch: &TwitchEventSubApi,
+  logins: &[String],
+) -> Result<Vec<LiveStream>, EventSubError> {
+  if logins.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?{}",
+    STREAMS_URL,
+    logins
+      .iter()
+      .take(100)
+      .map(|login| format!("user_login={}", login))
+      .collect::<Vec<_>>()
+      .join("&")
+  );
+
+  let response = TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .run()?;
+  let streams: LiveStreamsResponse =
+    serde_json::from_str(&response).map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+  Ok(streams.data)
+}
+
+#[derive(Deserialise, Clone, Debug)]
+pub struct CreatedOutcome {
+  pub id: String,
+  pub title: String,
+}
+
+#[derive(Deserialise, Clone, Debug)]
+pub struct CreatedVote {
+  pub id: String,
+  // Only predictions have outcomes, polls call them choices
+  #[serde(default)]
+  pub outcomes: Vec<CreatedOutcome>,
+}
+
+#[derive(Deserialise)]
+struct CreatedVoteResponse {
+  data: Vec<CreatedVote>,
+}
+
+fn send_vote(
+  twitch: &TwitchEventSubApi,
+  url: &str,
+  data: serde_json::Value,
+) -> Result<CreatedVote, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let response = TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .json_content()
+    .is_post(data.to_string())
+    .run()?;
+
+  let created: CreatedVoteResponse =
+    serde_json::from_str(&response).map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+  created
+    .data
+    .into_iter()
+    .next()
+    .ok_or(EventSubError::HttpFailed(response))
+}
+
+pub fn create_poll(
+  twitch: &TwitchEventSubApi,
+  title: &str,
+  choices: &[String],
+  seconds: u32,
+) -> Result<CreatedVote, EventSubError> {
+  let broadcaster_id = HelixAuth::from(twitch).broadcaster_id;
+  send_vote(
+    twitch,
+    POLLS_URL,
+    json!({
+      "broadcaster_id": broadcaster_id,
+      "title": title,
+      "choices": choices.iter().map(|choice| json!({ "title": choice })).collect::<Vec<_>>(),
+      "duration": seconds,
+    }),
+  )
+}
+
+pub fn end_poll(twitch: &TwitchEventSubApi, id: &str) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let data = json!({
+    "broadcaster_id": auth.broadcaster_id,
+    "id": id,
+    "status": "TERMINATED",
+  });
+
+  TwitchHttpRequest::new(POLLS_URL)
+    .full_auth(auth.access_token, auth.client_id)
+    .json_content()
+    .is_patch(data.to_string())
+    .run()
+}
+
+pub fn create_prediction(
+  twitch: &TwitchEventSubApi,
+  title: &str,
+  outcomes: &[String],
+  seconds: u32,
+) -> Result<CreatedVote, EventSubError> {
+  let broadcaster_id = HelixAuth::from(twitch).broadcaster_id;
+  send_vote(
+    twitch,
+    PREDICTIONS_URL,
+    json!({
+      "broadcaster_id": broadcaster_id,
+      "title": title,
+      "outcomes": outcomes.iter().map(|outcome| json!({ "title": outcome })).collect::<Vec<_>>(),
+      "prediction_window": seconds,
+    }),
+  )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PredictionStatus {
+  Locked,
+  Resolved,
+  // Refunds everyone's channel points
+  Canceled,
+}
+
+pub fn end_prediction(
+  twitch: &TwitchEventSubApi,
+  id: &str,
+  status: PredictionStatus,
+  winning_outcome_id: Option<&str>,
+) -> Result<String, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let status = match status {
+    PredictionStatus::Locked => "LOCKED",
+    PredictionStatus::Resolved => "RESOLVED",
+    PredictionStatus::Canceled => "CANCELED",
+  };
+  let mut data = json!({
+    "broadcaster_id": auth.broadcaster_id,
+    "id": id,
+    "status": status,
+  });
+  if let Some(winning_outcome_id) = winning_outcome_id {
+    data["winning_outcome_id"] = json!(winning_outcome_id);
+  }
+
+  TwitchHttpRequest::new(PREDICTIONS_URL)
+    .full_auth(auth.access_token, auth.client_id)
+    .json_content()
+    .is_patch(data.to_string())
+    .run()
+}
+
+#[derive(Deserialise)]
+struct Follower {
+  followed_at: String,
+}
+
+#[derive(Deserialise)]
+struct FollowersResponse {
+  data: Vec<Follower>,
+}
+
+// None when the user doesn't follow the channel
+pub fn get_followed_at<S: Into<String>>(
+  twitch: &TwitchEventSubApi,
+  user_id: S,
+) -> Result<Option<String>, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!(
+    "{}?broadcaster_id={}&user_id={}",
+    FOLLOWERS_URL,
+    auth.broadcaster_id,
+    user_id.into()
+  );
+
+  let response = TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .run()?;
+  let followers: FollowersResponse =
+    serde_json::from_str(&response).map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+  Ok(
+    followers
+      .data
+      .into_iter()
+      .next()
+      .map(|follower| follower.followed_at),
+  )
+}
+
+#[derive(Deserialise)]
+struct Cheermote {
+  prefix: String,
+}
+
+#[derive(Deserialise)]
+struct CheermotesResponse {
+  data: Vec<Cheermote>,
+}
+
+// Includes the global cheermotes as well as the channel's own
+pub fn get_cheermote_prefixes(twitch: &TwitchEventSubApi) -> Result<Vec<String>, EventSubError> {
+  let auth = HelixAuth::from(twitch);
+  let url = format!("{}?broadcaster_id={}", CHEERMOTES_URL, auth.broadcaster_id);
+
+  let response = TwitchHttpRequest::new(url)
+    .full_auth(auth.access_token, auth.client_id)
+    .run()?;
+  let cheermotes: CheermotesResponse =
+    serde_json::from_str(&response).map_err(|e| EventSubError::HttpFailed(e.to_string()))?;
+  Ok(
+    cheermotes
+      .data
+      .into_iter()
+      .map(|cheermote| cheermote.prefix)
+      .collect(),
+  )
+}
@@ -0,0 +1,201 @@
+use twitcheventsub::{
+  HypeTrainBeginData, HypeTrainEndData, HypeTrainProgressData, TopContributions, TwitchEventSubApi,
+};
+
+use crate::modules::{
+  clock::{parse_timestamp, unix_timestamp},
+  overlay::{OverlayEvent, OverlayServer},
+};
+
+const BAR_WIDTH: usize = 30;
+
+struct Contribution {
+  user: String,
+  kind: String,
+  total: u32,
+}
+
+impl Contribution {
+  fn from(contribution: &TopContributions) -> Contribution {
+    Contribution {
+      user: contribution.user.name.to_owned(),
+      kind: contribution.kind.to_owned(),
+      total: contribution.total,
+    }
+  }
+
+  fn describe(&self) -> String {
+    match self.kind.as_str() {
+      "bits" => format!("{} ({} bits)", self.user, self.total),
+      _ => format!("{} ({} points)", self.user, self.total),
+    }
+  }
+}
+
+struct HypeTrain {
+  level: u32,
+  progress: u32,
+  // Twitch leaves it out of the begin event
+  goal: Option<u32>,
+  total: u32,
+  top_contributions: Vec<Contribution>,
+  expires_at: Option<u64>,
+}
+
+impl HypeTrain {
+  fn bar(&self) -> String {
+    let filled = match self.goal {
+      Some(goal) if goal > 0 => (self.progress.min(goal) as usize * BAR_WIDTH) / goal as usize,
+      _ => 0,
+    };
+    format!("[{}{}]", "#".repeat(filled), ".".repeat(BAR_WIDTH - filled))
+  }
+
+  fn print(&self) {
+    let goal = self
+      .goal
+      .map(|goal| goal.to_string())
+      .unwrap_or("?".to_owned());
+    let time_left = match self.expires_at {
+      Some(expires_at) => {
+        let seconds = expires_at.saturating_sub(unix_timestamp());
+        format!(", {}m {}s left", seconds / 60, seconds % 60)
+      }
+      None => String::new(),
+    };
+    println!(
+      "Hype train level {} {} {}/{} ({} points total{})",
+      self.level,
+      self.bar(),
+      self.progress,
+      goal,
+      self.total,
+      time_left
+    );
+    if !self.top_contributions.is_empty() {
+      println!(
+        "    Top: {}",
+        self
+          .top_contributions
+          .iter()
+          .map(Contribution::describe)
+          .collect::<Vec<_>>()
+          .join(", ")
+      );
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct HypeTrainTracker {
+  train: Option<HypeTrain>,
+}
+
+impl HypeTrainTracker {
+  pub fn begin(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    overlay: &OverlayServer,
+    begin: &HypeTrainBeginData,
+  ) {
+    let train = HypeTrain {
+      level: begin.level,
+      progress: begin.progress,
+      goal: None,
+      total: begin.total,
+      top_contributions: begin
+        .top_contributions
+        .iter()
+        .map(Contribution::from)
+        .collect(),
+      expires_at: None,
+    };
+
+    let _ =
+      twitch.send_chat_message("A hype train has left the station! All aboard the Owl express!");
+    train.print();
+    overlay.send(OverlayEvent::HypeTrain {
+      level: train.level,
+      progress: train.progress,
+      goal: None,
+      ended: false,
+    });
+    self.train = Some(train);
+  }
+
+  pub fn progress(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    overlay: &OverlayServer,
+    progress: &HypeTrainProgressData,
+  ) {
+    let previous_level = self.train.as_ref().map(|train| train.level);
+    let train = HypeTrain {
+      level: progress.level,
+      progress: progress.progress,
+      goal: Some(progress.goal),
+      total: progress.total,
+      top_contributions: progress
+        .top_contributions
+        .iter()
+        .map(Contribution::from)
+        .collect(),
+      expires_at: parse_timestamp(&progress.expires_at),
+    };
+
+    // A missed begin event still gets announced once
+    if previous_level.is_none_or(|level| train.level > level) {
+      let _ = twitch.send_chat_message(format!(
+        "The hype train reached level {}! Thank you {}!",
+        train.level, progress.last_contribution.user.name
+      ));
+    }
+
+    train.print();
+    overlay.send(OverlayEvent::HypeTrain {
+      level: train.level,
+      progress: train.progress,
+      goal: train.goal,
+      ended: false,
+    });
+    self.train = Some(train);
+  }
+
+  pub fn end(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    overlay: &OverlayServer,
+    end: &HypeTrainEndData,
+  ) {
+    self.train = None;
+
+    let top = end
+      .top_contributions
+      .iter()
+      .map(|contribution| Contribution::from(contribution).describe())
+      .collect::<Vec<_>>();
+    let mut summary = format!(
+      "The hype train pulled in at level {} with {} points!",
+      end.level, end.total
+    );
+    if !top.is_empty() {
+      summary = format!("{} Top conductors: {}", summary, top.join(", "));
+    }
+
+    let _ = twitch.send_chat_message(summary.to_owned());
+    println!("{}", summary);
+    overlay.send(OverlayEvent::HypeTrain {
+      level: end.level,
+      progress: end.total,
+      goal: None,
+      ended: true,
+    });
+  }
+
+  pub fn print(&self) {
+    match &self.train {
+      Some(train) => train.print(),
+      None => println!("There is no hype train right now."),
+    }
+  }
+}
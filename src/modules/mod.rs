@@ -1,2 +1,32 @@
+pub mod ads;
+pub mod alerts;
+pub mod bot_guard;
+pub mod bot_users;
+pub mod cli;
+pub mod clock;
+pub mod config;
 pub mod consts;
+pub mod effects;
 pub mod emotes;
+pub mod filters;
+pub mod flood;
+pub mod followers;
+pub mod helix;
+pub mod hype_train;
+pub mod moderation;
+pub mod modlog;
+pub mod overlay;
+pub mod permits;
+pub mod polls;
+pub mod raids;
+pub mod redemptions;
+pub mod review;
+pub mod rewards;
+pub mod sliding_window;
+pub mod templates;
+pub mod terminal;
+pub mod thanks;
+pub mod tts;
+pub mod tts_filter;
+pub mod tts_queue;
+pub mod viewers;
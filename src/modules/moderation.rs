@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::TwitchEventSubApi;
+
+use crate::modules::{
+  clock::unix_timestamp,
+  consts::STRIKES_FILE,
+  filters::ChatFilters,
+  helix,
+  modlog::{self, ModLogEntry},
+};
+
+// A link alone is enough to reach the default spam threshold
+const LINK_SCORE: u32 = 2;
+
+#[derive(Clone)]
+pub struct SpamCheck {
+  pub score: u32,
+  pub matched_rules: Vec<String>,
+  pub has_blocked_term: bool,
+  pub has_link: bool,
+}
+
+impl SpamCheck {
+  pub fn check(message: &str, filters: &ChatFilters, links_permitted: bool) -> SpamCheck {
+    let lower_message = message.to_ascii_lowercase();
+    let mut score = 0;
+    let mut matched_rules = Vec::new();
+
+    let blocked_terms = filters.blocked_terms_in(&lower_message);
+    for term in &blocked_terms {
+      matched_rules.push(format!("blocked term: {}", term));
+    }
+
+    let links = if links_permitted {
+      Vec::new()
+    } else {
+      filters.disallowed_links(&lower_message)
+    };
+    if !links.is_empty() {
+      score += LINK_SCORE;
+      matched_rules.push(format!("link: {}", links.join(" ")));
+    }
+
+    // Allowed links shouldn't make the rest of the message look sus
+    let unlinked_message = filters.without_links(&lower_message, links_permitted);
+    // Sus words are stems ("promot"), matched at the start of a word so
+    // "view" catches "viewers" but not "review"
+    let words = unlinked_message
+      .split(|c: char| !c.is_alphanumeric())
+      .filter(|word| !word.is_empty())
+      .collect::<Vec<_>>();
+    for sussy in filters
+      .sus_words
+      .iter()
+      .filter(|sussy| words.iter().any(|word| word.starts_with(sussy.as_str())))
+    {
+      score += 1;
+      matched_rules.push(format!("sus word: {}", sussy));
+    }
+
+    SpamCheck {
+      score,
+      matched_rules,
+      has_blocked_term: !blocked_terms.is_empty(),
+      has_link: !links.is_empty(),
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct FlaggedMessage {
+  pub username: String,
+  pub user_id: String,
+  pub message_id: String,
+  pub text: String,
+  pub spam_check: SpamCheck,
+}
+
+#[derive(Serialise, Deserialise, Clone, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ModerationAction {
+  Delete,
+  Timeout { seconds: u32 },
+  Ban,
+}
+
+impl fmt::Display for ModerationAction {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ModerationAction::Delete => write!(f, "deleted"),
+      ModerationAction::Timeout { seconds } => write!(f, "timed out for {}s", seconds),
+      ModerationAction::Ban => write!(f, "banned"),
+    }
+  }
+}
+
+impl ModerationAction {
+  pub fn apply(
+    &self,
+    twitch: &mut TwitchEventSubApi,
+    user_id: &str,
+    message_id: &str,
+    reason: &str,
+  ) -> bool {
+    let deleted = twitch.delete_message(message_id).is_ok();
+    match self {
+      ModerationAction::Delete => deleted,
+      ModerationAction::Timeout { seconds } => {
+        helix::timeout_user(twitch, user_id, *seconds, reason).is_ok()
+      }
+      ModerationAction::Ban => helix::ban_user(twitch, user_id, reason).is_ok(),
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct ModerationConfig {
+  // First time chatter messages scoring at least spam_threshold are moderated,
+  // ones scoring at least review_threshold are held for a moderator to review
+  pub spam_threshold: u32,
+  pub review_threshold: u32,
+  // Strike n uses the nth action, anything past the end repeats the last one
+  pub escalation: Vec<ModerationAction>,
+  // Strikes are forgotten after this many hours without a new one, 0 keeps them forever
+  pub strike_expiry_hours: u64,
+}
+
+impl Default for ModerationConfig {
+  fn default() -> Self {
+    ModerationConfig {
+      spam_threshold: 2,
+      review_threshold: 1,
+      escalation: vec![
+        ModerationAction::Delete,
+        ModerationAction::Timeout { seconds: 600 },
+        ModerationAction::Ban,
+      ],
+      strike_expiry_hours: 24 * 30,
+    }
+  }
+}
+
+impl ModerationConfig {
+  pub fn action_for(&self, strikes: u32) -> ModerationAction {
+    let idx = (strikes.max(1) as usize - 1).min(self.escalation.len().saturating_sub(1));
+    self
+      .escalation
+      .get(idx)
+      .cloned()
+      .unwrap_or(ModerationAction::Delete)
+  }
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+pub struct StrikeRecord {
+  pub user_id: String,
+  pub strikes: u32,
+  pub last_strike: u64,
+}
+
+#[derive(Serialise, Deserialise, Default)]
+pub struct Strikes {
+  users: HashMap<String, StrikeRecord>,
+}
+
+impl Strikes {
+  pub fn load() -> Strikes {
+    let mut strikes = Strikes::default();
+    if let Ok(strikes_string) = fs::read_to_string(STRIKES_FILE) {
+      strikes = serde_json::from_str(&strikes_string).unwrap();
+    }
+
+    strikes
+  }
+
+  pub fn save(&self) {
+    let mut file = fs::File::create(STRIKES_FILE).unwrap();
+    if let Ok(strikes) = serde_json::to_string(self) {
+      file.write_all(strikes.as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+  }
+
+  pub fn add_strike<S: Into<String>, T: Into<String>>(
+    &mut self,
+    username: S,
+    user_id: T,
+    expiry_hours: u64,
+  ) -> u32 {
+    let now = unix_timestamp();
+    let record = self
+      .users
+      .entry(username.into().to_lowercase())
+      .or_insert(StrikeRecord {
+        user_id: user_id.into(),
+        strikes: 0,
+        last_strike: now,
+      });
+
+    if expiry_hours > 0 && now.saturating_sub(record.last_strike) > expiry_hours * 60 * 60 {
+      record.strikes = 0;
+    }
+
+    record.strikes += 1;
+    record.last_strike = now;
+    record.strikes
+  }
+
+  // Strikes the user and applies the escalated action, unless a moderator
+  // has already chosen what should happen to them
+  pub fn punish(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    config: &ModerationConfig,
+    flagged: &FlaggedMessage,
+    chosen_action: Option<ModerationAction>,
+  ) -> bool {
+    let strike_count = self.add_strike(
+      &flagged.username,
+      &flagged.user_id,
+      config.strike_expiry_hours,
+    );
+    self.save();
+
+    let action = chosen_action.unwrap_or_else(|| config.action_for(strike_count));
+    let succeeded = action.apply(
+      twitch,
+      &flagged.user_id,
+      &flagged.message_id,
+      "Sent to Owlkatraz by OwlBot",
+    );
+    modlog::append(&ModLogEntry::new(flagged, action, strike_count, succeeded));
+
+    succeeded
+  }
+
+  fn strike_count(&self, username: &str) -> u32 {
+    self
+      .users
+      .get(&username.to_lowercase())
+      .map(|record| record.strikes)
+      .unwrap_or(0)
+  }
+
+  // Deletes the message without striking the user, for chatters that
+  // aren't being treated as bots
+  pub fn remove(&self, twitch: &mut TwitchEventSubApi, flagged: &FlaggedMessage) -> bool {
+    let action = ModerationAction::Delete;
+    let succeeded = action.apply(
+      twitch,
+      &flagged.user_id,
+      &flagged.message_id,
+      "Removed by OwlBot",
+    );
+    modlog::append(&ModLogEntry::new(
+      flagged,
+      action,
+      self.strike_count(&flagged.username),
+      succeeded,
+    ));
+
+    succeeded
+  }
+
+  pub fn pardon<S: Into<String>>(&mut self, username: S) -> bool {
+    self.users.remove(&username.into().to_lowercase()).is_some()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn score(message: &str) -> u32 {
+    SpamCheck::check(message, &ChatFilters::default(), false).score
+  }
+
+  #[test]
+  fn sus_words_match_word_starts() {
+    assert_eq!(score("cheap viewers here"), 2);
+    assert_eq!(score("promotion activation"), 2);
+  }
+
+  #[test]
+  fn sus_words_inside_other_words_are_ignored() {
+    assert_eq!(score("can you review my code"), 0);
+    assert_eq!(score("the overview is carefree"), 0);
+  }
+}
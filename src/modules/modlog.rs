@@ -0,0 +1,108 @@
+use std::fmt;
+use std::fs;
+use std::io::Write;
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+
+use crate::modules::{
+  clock::{format_timestamp, unix_timestamp},
+  consts::MODLOG_FILE,
+  moderation::{FlaggedMessage, ModerationAction},
+};
+
+#[derive(Serialise, Deserialise, Clone)]
+pub struct ModLogEntry {
+  pub timestamp: u64,
+  pub user: String,
+  pub user_id: String,
+  pub message: String,
+  pub matched_rules: Vec<String>,
+  pub score: u32,
+  pub action: ModerationAction,
+  pub strikes: u32,
+  pub succeeded: bool,
+}
+
+impl ModLogEntry {
+  pub fn new(
+    flagged: &FlaggedMessage,
+    action: ModerationAction,
+    strikes: u32,
+    succeeded: bool,
+  ) -> ModLogEntry {
+    ModLogEntry {
+      timestamp: unix_timestamp(),
+      user: flagged.username.to_owned(),
+      user_id: flagged.user_id.to_owned(),
+      message: flagged.text.to_owned(),
+      matched_rules: flagged.spam_check.matched_rules.to_owned(),
+      score: flagged.spam_check.score,
+      action,
+      strikes,
+      succeeded,
+    }
+  }
+
+  // Only the kind of each rule, the details are the blocked terms and
+  // links themselves which shouldn't be posted back into chat
+  pub fn chat_summary(&self) -> String {
+    let mut rules = Vec::new();
+    for rule in &self.matched_rules {
+      let kind = rule.split(':').next().unwrap_or(rule);
+      if !rules.contains(&kind) {
+        rules.push(kind);
+      }
+    }
+
+    format!(
+      "{} {} (strike {}, score {}: {})",
+      self.user,
+      self.action,
+      self.strikes,
+      self.score,
+      rules.join(", ")
+    )
+  }
+}
+
+impl fmt::Display for ModLogEntry {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "[{}] {} ({}) {}{} - strike {}, score {} [{}]\n    {}",
+      format_timestamp(self.timestamp),
+      self.user,
+      self.user_id,
+      self.action,
+      if self.succeeded { "" } else { " (FAILED)" },
+      self.strikes,
+      self.score,
+      self.matched_rules.join(", "),
+      self.message
+    )
+  }
+}
+
+pub fn append(entry: &ModLogEntry) {
+  let mut file = fs::File::options()
+    .append(true)
+    .create(true)
+    .open(MODLOG_FILE)
+    .unwrap();
+  if let Ok(entry) = serde_json::to_string(entry) {
+    file.write_all(format!("{}\n", entry).as_bytes()).unwrap();
+  }
+}
+
+pub fn recent(count: usize) -> Vec<ModLogEntry> {
+  let mut entries = Vec::new();
+  if let Ok(modlog) = fs::read_to_string(MODLOG_FILE) {
+    entries = modlog
+      .lines()
+      .filter_map(|line| serde_json::from_str::<ModLogEntry>(line).ok())
+      .collect::<Vec<_>>();
+  }
+
+  let skip = entries.len().saturating_sub(count);
+  entries.split_off(skip)
+}
@@ -0,0 +1,184 @@
+use std::fs;
+use std::io::Write;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::error;
+use websocket::sync::{Client, Server};
+use websocket::OwnedMessage;
+
+use crate::modules::filters::ChatFilters;
+
+const OVERLAY_PAGE: &str = include_str!("overlay.html");
+
+// A stuck browser source shouldn't hold up the bot
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct OverlayConfig {
+  pub enabled: bool,
+  // The page and websocket share this address, point OBS at http://<address>/
+  pub address: String,
+  // Served instead of the built in page when set
+  pub page: Option<PathBuf>,
+}
+
+impl Default for OverlayConfig {
+  fn default() -> Self {
+    OverlayConfig {
+      enabled: true,
+      address: "127.0.0.1:7411".to_owned(),
+      page: None,
+    }
+  }
+}
+
+// Sent to the overlay as JSON, with the variant name in "type"
+#[derive(Serialise, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OverlayEvent {
+  Follow {
+    user: String,
+  },
+  Raid {
+    user: String,
+    viewers: u32,
+  },
+  Subscription {
+    user: String,
+    tier: String,
+    months: u32,
+    gifted: bool,
+    message: Option<String>,
+  },
+  GiftSubs {
+    user: Option<String>,
+    count: u32,
+    tier: String,
+    recipients: Vec<String>,
+  },
+  Cheer {
+    user: Option<String>,
+    bits: u32,
+    message: String,
+  },
+  Redeem {
+    user: String,
+    reward: String,
+    input: String,
+  },
+  HypeTrain {
+    level: u32,
+    progress: u32,
+    // Twitch only includes the goal once the train is moving
+    goal: Option<u32>,
+    ended: bool,
+  },
+  RankUp {
+    user: String,
+    points: u32,
+  },
+  // Alerts posted in chat by bots like Streamlabs
+  BotAlert {
+    user: String,
+    message: String,
+  },
+}
+
+// Viewer text goes on stream, so it has to get past the same blocked terms
+// as TTS. Text with a blocked term is left out and links are dropped.
+pub fn viewer_text(text: &str, filters: &ChatFilters) -> String {
+  if !filters.blocked_terms_in(&text.to_lowercase()).is_empty() {
+    return String::new();
+  }
+
+  text
+    .split_whitespace()
+    .filter(|word| filters.disallowed_links(&word.to_lowercase()).is_empty())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+// Serves the overlay page over http and pushes events to every page that
+// opens a websocket on the same address
+pub struct OverlayServer {
+  clients: Arc<Mutex<Vec<Client<TcpStream>>>>,
+}
+
+impl OverlayServer {
+  // Events are quietly dropped when the overlay is disabled or couldn't start
+  pub fn start(config: &OverlayConfig) -> OverlayServer {
+    let clients = Arc::new(Mutex::new(Vec::new()));
+    if !config.enabled {
+      return OverlayServer { clients };
+    }
+
+    let page = match &config.page {
+      Some(path) => match fs::read_to_string(path) {
+        Ok(page) => page,
+        Err(e) => {
+          error!("Failed to read overlay page {}: {}", path.display(), e);
+          OVERLAY_PAGE.to_owned()
+        }
+      },
+      None => OVERLAY_PAGE.to_owned(),
+    };
+
+    let mut server = match Server::bind(&config.address) {
+      Ok(server) => server,
+      Err(e) => {
+        println!("Overlay couldn't start on {}: {}", config.address, e);
+        error!("Overlay couldn't start on {}: {}", config.address, e);
+        return OverlayServer { clients };
+      }
+    };
+    println!("Overlay running at http://{}/", config.address);
+
+    let accepted = clients.clone();
+    thread::spawn(move || loop {
+      match server.accept() {
+        Ok(upgrade) => match upgrade.accept() {
+          Ok(client) => {
+            let _ = client.stream_ref().set_write_timeout(Some(WRITE_TIMEOUT));
+            accepted.lock().unwrap().push(client);
+          }
+          Err((_, e)) => error!("Overlay websocket handshake failed: {}", e),
+        },
+        // Anything that isn't a websocket is asking for the page
+        Err(connection) => {
+          if let Some(mut stream) = connection.stream {
+            let _ = stream.write_all(
+              format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                page.len(),
+                page
+              )
+              .as_bytes(),
+            );
+          }
+        }
+      }
+    });
+
+    OverlayServer { clients }
+  }
+
+  // Overlays that have been closed are dropped once sending to them fails
+  pub fn send(&self, event: OverlayEvent) {
+    let Ok(json) = serde_json::to_string(&event) else {
+      return;
+    };
+
+    let message = OwnedMessage::Text(json);
+    self
+      .clients
+      .lock()
+      .unwrap()
+      .retain_mut(|client| client.send_message(&message).is_ok());
+  }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// How long a single link permit waits to be used
+const SINGLE_LINK_SECONDS: u64 = 300;
+
+struct Permit {
+  expires: Instant,
+  single_link: bool,
+}
+
+// Lets a moderator !permit someone to post links past the spam filter
+#[derive(Default)]
+pub struct Permits {
+  permits: HashMap<String, Permit>,
+}
+
+impl Permits {
+  pub fn grant<S: Into<String>>(&mut self, username: S, seconds: Option<u64>, now: Instant) {
+    let username = username.into().to_lowercase();
+    let permit = Permit {
+      expires: now + Duration::from_secs(seconds.unwrap_or(SINGLE_LINK_SECONDS)),
+      single_link: seconds.is_none(),
+    };
+    match seconds {
+      Some(seconds) => println!("Permit: {} can post links for {}s", username, seconds),
+      None => println!("Permit: {} can post one link", username),
+    }
+    self.permits.insert(username, permit);
+  }
+
+  pub fn use_link(&mut self, username: &str, now: Instant) -> bool {
+    let username = username.to_lowercase();
+    let Some(permit) = self.permits.get(&username) else {
+      return false;
+    };
+
+    if permit.expires <= now {
+      self.permits.remove(&username);
+      return false;
+    }
+
+    if permit.single_link {
+      self.permits.remove(&username);
+      println!("Permit: {} used their link", username);
+    }
+    true
+  }
+
+  pub fn expire(&mut self, now: Instant) {
+    self.permits.retain(|username, permit| {
+      let active = permit.expires > now;
+      if !active {
+        println!("Permit: {}'s link permit expired", username);
+      }
+      active
+    });
+  }
+
+  pub fn print(&self, now: Instant) {
+    if self.permits.is_empty() {
+      println!("Nobody has a link permit.");
+    }
+    for (username, permit) in &self.permits {
+      println!(
+        "Permit: {} {} ({}s left)",
+        username,
+        if permit.single_link {
+          "one link"
+        } else {
+          "any links"
+        },
+        permit.expires.saturating_duration_since(now).as_secs()
+      );
+    }
+  }
+}
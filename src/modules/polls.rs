@@ -0,0 +1,405 @@
+use twitcheventsub::{Choices, Outcome, TwitchEventSubApi};
+
+use crate::modules::helix::{self, CreatedOutcome, PredictionStatus};
+
+const BAR_WIDTH: usize = 20;
+const MAX_OPTION_LENGTH: usize = 25;
+
+// What twitch accepts for each
+struct VoteLimits {
+  max_title_length: usize,
+  max_options: usize,
+  min_seconds: u32,
+  max_seconds: u32,
+  default_seconds: u32,
+}
+
+const POLL_LIMITS: VoteLimits = VoteLimits {
+  max_title_length: 60,
+  max_options: 5,
+  min_seconds: 15,
+  max_seconds: 1800,
+  default_seconds: 60,
+};
+
+const PREDICTION_LIMITS: VoteLimits = VoteLimits {
+  max_title_length: 45,
+  max_options: 10,
+  min_seconds: 30,
+  max_seconds: 1800,
+  default_seconds: 120,
+};
+
+// Prediction point totals easily pass what u32 can hold times 100
+fn bar(value: u64, total: u64) -> String {
+  let filled = (value.min(total) * BAR_WIDTH as u64)
+    .checked_div(total)
+    .unwrap_or(0) as usize;
+  format!("[{}{}]", "#".repeat(filled), ".".repeat(BAR_WIDTH - filled))
+}
+
+fn percent(value: u64, total: u64) -> u64 {
+  (value * 100).checked_div(total).unwrap_or(0)
+}
+
+pub struct VoteRequest {
+  pub title: String,
+  pub options: Vec<String>,
+  pub seconds: u32,
+}
+
+impl VoteRequest {
+  // "Question" option | option [seconds], parsed from the message before it
+  // is lowercased so the options keep their case
+  fn parse(text: &str, limits: &VoteLimits) -> Result<VoteRequest, String> {
+    let Some(rest) = text.trim().strip_prefix('"') else {
+      return Err("Put the question in quotes.".to_owned());
+    };
+    let Some((title, rest)) = rest.split_once('"') else {
+      return Err("The question is missing its closing quote.".to_owned());
+    };
+
+    let mut options = rest
+      .split('|')
+      .map(|option| option.trim().to_owned())
+      .collect::<Vec<_>>();
+    let mut seconds = limits.default_seconds;
+    if let Some(last) = options.last_mut() {
+      if let Some((option, duration)) = last.rsplit_once(char::is_whitespace) {
+        if let Ok(duration) = duration.parse::<u32>() {
+          seconds = duration;
+          *last = option.trim_end().to_owned();
+        }
+      }
+    }
+    options.retain(|option| !option.is_empty());
+
+    let title = title.trim();
+    if title.is_empty() || title.len() > limits.max_title_length {
+      return Err(format!(
+        "The question needs to be 1 to {} characters.",
+        limits.max_title_length
+      ));
+    }
+    if options.len() < 2 || options.len() > limits.max_options {
+      return Err(format!(
+        "There needs to be 2 to {} options separated by |.",
+        limits.max_options
+      ));
+    }
+    if let Some(option) = options
+      .iter()
+      .find(|option| option.len() > MAX_OPTION_LENGTH)
+    {
+      return Err(format!(
+        "\"{}\" is too long, options can be {} characters.",
+        option, MAX_OPTION_LENGTH
+      ));
+    }
+
+    Ok(VoteRequest {
+      title: title.to_owned(),
+      options,
+      seconds: seconds.clamp(limits.min_seconds, limits.max_seconds),
+    })
+  }
+}
+
+struct ActivePrediction {
+  id: String,
+  outcomes: Vec<CreatedOutcome>,
+}
+
+// Follows the current poll and prediction, whether they were started by the
+// bot or from the dashboard
+#[derive(Default)]
+pub struct PollTracker {
+  poll_id: Option<String>,
+  prediction: Option<ActivePrediction>,
+}
+
+impl PollTracker {
+  pub fn poll_command(&mut self, twitch: &TwitchEventSubApi, text: &str) -> String {
+    if text.trim().eq_ignore_ascii_case("end") {
+      let Some(id) = &self.poll_id else {
+        return "There is no poll running.".to_owned();
+      };
+      return match helix::end_poll(twitch, id) {
+        Ok(_) => "Ending the poll.".to_owned(),
+        Err(e) => format!("Couldn't end the poll: {:?}", e),
+      };
+    }
+
+    let poll = match VoteRequest::parse(text, &POLL_LIMITS) {
+      Ok(poll) => poll,
+      Err(e) => return format!("{} Usage: !poll \"Question\" option | option [seconds]", e),
+    };
+    match helix::create_poll(twitch, &poll.title, &poll.options, poll.seconds) {
+      Ok(created) => {
+        self.poll_id = Some(created.id);
+        format!("Poll started for {} seconds!", poll.seconds)
+      }
+      Err(e) => format!("Couldn't start the poll: {:?}", e),
+    }
+  }
+
+  pub fn predict_command(&mut self, twitch: &TwitchEventSubApi, text: &str) -> String {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let (status, winner) = match words[..] {
+      ["lock"] => (PredictionStatus::Locked, None),
+      ["cancel"] => (PredictionStatus::Canceled, None),
+      ["win", number] => match number.parse::<usize>() {
+        Ok(number) if number > 0 => (PredictionStatus::Resolved, Some(number - 1)),
+        _ => return "Usage: !predict win <outcome number>".to_owned(),
+      },
+      _ => return self.start_prediction(twitch, text),
+    };
+
+    let Some(prediction) = &self.prediction else {
+      return "There is no prediction running.".to_owned();
+    };
+    let winning_outcome = match winner {
+      Some(winner) => match prediction.outcomes.get(winner) {
+        Some(outcome) => Some(outcome),
+        None => return format!("Pick an outcome from 1 to {}.", prediction.outcomes.len()),
+      },
+      None => None,
+    };
+
+    match helix::end_prediction(
+      twitch,
+      &prediction.id,
+      status,
+      winning_outcome.map(|outcome| outcome.id.as_str()),
+    ) {
+      Ok(_) => match (status, winning_outcome) {
+        (PredictionStatus::Locked, _) => "Predictions are locked!".to_owned(),
+        (_, Some(outcome)) => format!("\"{}\" wins the prediction!", outcome.title),
+        _ => "The prediction has been cancelled.".to_owned(),
+      },
+      Err(e) => format!("Couldn't update the prediction: {:?}", e),
+    }
+  }
+
+  fn start_prediction(&mut self, twitch: &TwitchEventSubApi, text: &str) -> String {
+    let prediction = match VoteRequest::parse(text, &PREDICTION_LIMITS) {
+      Ok(prediction) => prediction,
+      Err(e) => {
+        return format!(
+          "{} Usage: !predict \"Question\" outcome | outcome [seconds] or !predict lock|cancel|win <number>",
+          e
+        )
+      }
+    };
+
+    match helix::create_prediction(
+      twitch,
+      &prediction.title,
+      &prediction.options,
+      prediction.seconds,
+    ) {
+      Ok(created) => {
+        self.prediction = Some(ActivePrediction {
+          id: created.id,
+          outcomes: created.outcomes,
+        });
+        format!("Predictions are open for {} seconds!", prediction.seconds)
+      }
+      Err(e) => format!("Couldn't start the prediction: {:?}", e),
+    }
+  }
+
+  pub fn poll_begin(&mut self, id: &str, title: &str) {
+    self.poll_id = Some(id.to_owned());
+    println!("Poll started: {}", title);
+  }
+
+  pub fn poll_progress(&self, title: &str, choices: &[Choices]) {
+    let total = choices
+      .iter()
+      .map(|choice| u64::from(choice.votes))
+      .sum::<u64>();
+    println!("Poll: {} ({} votes)", title, total);
+    for choice in choices {
+      println!(
+        "  {} {:>3}% {} ({})",
+        bar(choice.votes.into(), total),
+        percent(choice.votes.into(), total),
+        choice.title,
+        choice.votes
+      );
+    }
+  }
+
+  pub fn poll_end(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    title: &str,
+    choices: &[Choices],
+    status: &str,
+  ) {
+    self.poll_id = None;
+    // Archived polls have already been announced when they completed
+    if status.eq_ignore_ascii_case("archived") {
+      return;
+    }
+
+    self.poll_progress(title, choices);
+    let total = choices
+      .iter()
+      .map(|choice| u64::from(choice.votes))
+      .sum::<u64>();
+    let most_votes = choices.iter().map(|choice| choice.votes).max().unwrap_or(0);
+    let winners = choices
+      .iter()
+      .filter(|choice| choice.votes == most_votes)
+      .map(|choice| format!("\"{}\"", choice.title))
+      .collect::<Vec<_>>();
+
+    let result_text = if total == 0 {
+      format!("The poll \"{}\" ended without any votes.", title)
+    } else if winners.len() > 1 {
+      format!(
+        "The poll \"{}\" ended in a tie between {} with {} votes each!",
+        title,
+        winners.join(" and "),
+        most_votes
+      )
+    } else {
+      format!(
+        "The poll \"{}\" is over! {} won with {} votes ({}%).",
+        title,
+        winners.join(""),
+        most_votes,
+        percent(most_votes.into(), total)
+      )
+    };
+    let _ = twitch.send_chat_message(result_text.to_owned());
+    println!("{}", result_text);
+  }
+
+  pub fn prediction_begin(&mut self, id: &str, title: &str) {
+    if self
+      .prediction
+      .as_ref()
+      .is_none_or(|prediction| prediction.id != id)
+    {
+      // The outcome ids arrive with the first progress event
+      self.prediction = Some(ActivePrediction {
+        id: id.to_owned(),
+        outcomes: Vec::new(),
+      });
+    }
+    println!("Prediction started: {}", title);
+  }
+
+  pub fn prediction_progress(&mut self, id: &str, title: &str, outcomes: &[Outcome]) {
+    self.prediction = Some(ActivePrediction {
+      id: id.to_owned(),
+      outcomes: outcomes
+        .iter()
+        .map(|outcome| CreatedOutcome {
+          id: outcome.id.to_owned(),
+          title: outcome.title.to_owned(),
+        })
+        .collect(),
+    });
+
+    let total = outcomes
+      .iter()
+      .map(|outcome| u64::from(outcome.channel_points))
+      .sum::<u64>();
+    println!("Prediction: {} ({} points)", title, total);
+    for (i, outcome) in outcomes.iter().enumerate() {
+      println!(
+        "  {}. {} {:>3}% {} ({} predictors, {} points)",
+        i + 1,
+        bar(outcome.channel_points.into(), total),
+        percent(outcome.channel_points.into(), total),
+        outcome.title,
+        outcome.users,
+        outcome.channel_points
+      );
+    }
+  }
+
+  pub fn prediction_lock(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    id: &str,
+    title: &str,
+    outcomes: &[Outcome],
+  ) {
+    self.prediction_progress(id, title, outcomes);
+    let _ = twitch.send_chat_message(format!(
+      "Predictions for \"{}\" are locked, good luck!",
+      title
+    ));
+  }
+
+  pub fn prediction_end(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    title: &str,
+    winning_outcome_id: &str,
+    outcomes: &[Outcome],
+    status: &str,
+  ) {
+    self.prediction = None;
+
+    let result_text = match outcomes
+      .iter()
+      .find(|outcome| outcome.id == winning_outcome_id)
+    {
+      Some(winner) if status.eq_ignore_ascii_case("resolved") => {
+        let total = outcomes
+          .iter()
+          .map(|outcome| u64::from(outcome.channel_points))
+          .sum::<u64>();
+        let mut result_text = format!(
+          "\"{}\" won the prediction \"{}\"! {} predictors split {} points.",
+          winner.title, title, winner.users, total
+        );
+        if let Some(top) = winner
+          .top_predictors
+          .iter()
+          .max_by_key(|predictor| predictor.channel_points_won.unwrap_or(0))
+        {
+          result_text = format!(
+            "{} Biggest win: {} (+{})",
+            result_text,
+            top.user.name,
+            top.channel_points_won.unwrap_or(0)
+          );
+        }
+        result_text
+      }
+      _ => format!(
+        "The prediction \"{}\" was cancelled, everyone's points have been refunded.",
+        title
+      ),
+    };
+    let _ = twitch.send_chat_message(result_text.to_owned());
+    println!("{}", result_text);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn percent_handles_large_point_totals() {
+    assert_eq!(percent(30_000_000, 60_000_000), 50);
+    assert_eq!(percent(1, 0), 0);
+  }
+
+  #[test]
+  fn bar_fills_in_proportion() {
+    assert_eq!(
+      bar(50_000_000, 100_000_000).matches('#').count(),
+      BAR_WIDTH / 2
+    );
+    assert_eq!(bar(1, 0).matches('#').count(), 0);
+  }
+}
@@ -0,0 +1,375 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::{error, EventSubscription, Subscription, Transport, TwitchEventSubApi};
+
+use crate::modules::{
+  clock::{format_timestamp, unix_timestamp},
+  consts::RAIDERS_FILE,
+  helix, templates,
+};
+
+// Twitch allows one shoutout every 2 minutes, and the same channel once an hour
+const SHOUTOUT_COOLDOWN: Duration = Duration::from_secs(2 * 60);
+const SAME_CHANNEL_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+const SHOUTOUT_ATTEMPTS: u32 = 3;
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct RaidConfig {
+  // Smaller raids are still welcomed, just not shouted out
+  pub shoutout_min_viewers: u32,
+  // Logins that are always suggested as raid targets
+  pub friends: Vec<String>,
+  // How many of the most recent raiders are suggested too
+  pub recent_raiders: usize,
+  // Posted to chat for viewers to copy, {target} is who we are raiding
+  pub raid_message: String,
+}
+
+impl Default for RaidConfig {
+  fn default() -> Self {
+    RaidConfig {
+      shoutout_min_viewers: 5,
+      friends: Vec::new(),
+      recent_raiders: 10,
+      raid_message: "The Owl Parliament has arrived! Hoot hoot {target}!".to_owned(),
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Default, Clone)]
+#[serde(default)]
+pub struct Raider {
+  pub login: String,
+  pub name: String,
+  pub raids: u32,
+  pub first_raid: u64,
+  pub last_raid: u64,
+  pub last_viewers: u32,
+}
+
+// Everyone who has raided the channel, keyed by broadcaster id so it
+// survives name changes
+#[derive(Serialise, Deserialise, Default)]
+pub struct Raiders {
+  raiders: HashMap<String, Raider>,
+}
+
+impl Raiders {
+  pub fn load() -> Raiders {
+    let mut raiders = Raiders::default();
+    if let Ok(raiders_string) = fs::read_to_string(RAIDERS_FILE) {
+      raiders = serde_json::from_str(&raiders_string).unwrap();
+    }
+
+    raiders
+  }
+
+  pub fn save(&self) {
+    let mut file = fs::File::create(RAIDERS_FILE).unwrap();
+    if let Ok(raiders) = serde_json::to_string(self) {
+      file.write_all(raiders.as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+  }
+
+  pub fn record(&mut self, broadcaster_id: &str, login: &str, name: &str, viewers: u32) -> Raider {
+    let now = unix_timestamp();
+    let raider = self
+      .raiders
+      .entry(broadcaster_id.to_owned())
+      .or_insert_with(|| Raider {
+        first_raid: now,
+        ..Default::default()
+      });
+
+    if raider.raids > 0 {
+      println!(
+        "{} has raided {} times before, last on {} with {} viewers",
+        name,
+        raider.raids,
+        format_timestamp(raider.last_raid),
+        raider.last_viewers
+      );
+    }
+
+    raider.login = login.to_owned();
+    raider.name = name.to_owned();
+    raider.raids += 1;
+    raider.last_raid = now;
+    raider.last_viewers = viewers;
+    let raider = raider.clone();
+    self.save();
+    raider
+  }
+
+  pub fn recent(&self, count: usize) -> Vec<&Raider> {
+    let mut raiders = self
+      .raiders
+      .values()
+      .filter(|raider| !raider.login.is_empty())
+      .collect::<Vec<_>>();
+    raiders.sort_by_key(|raider| std::cmp::Reverse(raider.last_raid));
+    raiders.truncate(count);
+    raiders
+  }
+}
+
+// The library has no permission for raids yet, a subscription without a tag
+// only asks for its scope
+pub fn raid_permission() -> Subscription {
+  Subscription::Custom((
+    String::new(),
+    "channel:manage:raids".to_owned(),
+    EventSubscription::new(&Subscription::PermissionIRCRead, Transport::new("")),
+  ))
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum RaidCommand {
+  Suggest,
+  Start(String),
+  Cancel,
+}
+
+impl RaidCommand {
+  pub fn parse(words: &[&str]) -> Option<RaidCommand> {
+    match words {
+      [] => Some(RaidCommand::Suggest),
+      ["cancel"] => Some(RaidCommand::Cancel),
+      [target] => Some(RaidCommand::Start(
+        target.trim_start_matches('@').to_lowercase(),
+      )),
+      _ => None,
+    }
+  }
+
+  // Used by both the terminal and the command line
+  pub fn run(self, twitch: &mut TwitchEventSubApi, config: &RaidConfig, raiders: &Raiders) {
+    let result = match self {
+      RaidCommand::Suggest => {
+        suggest_targets(twitch, config, raiders);
+        return;
+      }
+      RaidCommand::Start(target) => start_raid(twitch, config, &target),
+      RaidCommand::Cancel => helix::cancel_raid(twitch)
+        .map(|_| "The raid has been cancelled.".to_owned())
+        .map_err(|e| format!("Couldn't cancel the raid: {:?}", e)),
+    };
+
+    match result {
+      Ok(text) | Err(text) => println!("{}", text),
+    }
+  }
+}
+
+fn suggest_targets(twitch: &TwitchEventSubApi, config: &RaidConfig, raiders: &Raiders) {
+  let recent = raiders.recent(config.recent_raiders);
+  let mut logins = config
+    .friends
+    .iter()
+    .map(|friend| friend.to_lowercase())
+    .collect::<Vec<_>>();
+  for raider in &recent {
+    if !logins.contains(&raider.login) {
+      logins.push(raider.login.to_owned());
+    }
+  }
+
+  if logins.is_empty() {
+    println!("No one to suggest, add some friends to the raids section of the config.");
+    return;
+  }
+
+  let live = match helix::get_live_streams(twitch, &logins) {
+    Ok(live) => live,
+    Err(e) => {
+      println!("Couldn't check who is live: {:?}", e);
+      return;
+    }
+  };
+
+  if live.is_empty() {
+    println!(
+      "None of the {} friends and recent raiders are live.",
+      logins.len()
+    );
+  }
+  for stream in live {
+    let reason = match recent
+      .iter()
+      .find(|raider| raider.login == stream.user_login)
+    {
+      Some(raider) => format!(
+        "raided us {} times, last on {}",
+        raider.raids,
+        format_timestamp(raider.last_raid)
+      ),
+      None => "friend".to_owned(),
+    };
+    println!(
+      "  raid {} | {} with {} viewers ({})",
+      stream.user_login, stream.game_name, stream.viewer_count, reason
+    );
+  }
+}
+
+fn start_raid(
+  twitch: &mut TwitchEventSubApi,
+  config: &RaidConfig,
+  target: &str,
+) -> Result<String, String> {
+  let users = twitch
+    .get_users_from_logins(vec![target])
+    .map_err(|e| format!("Couldn't look up {}: {:?}", target, e))?
+    .data;
+  let Some(user) = users.first() else {
+    return Err(format!("There is no channel called {}.", target));
+  };
+
+  helix::start_raid(twitch, &user.id).map_err(|e| format!("Couldn't start the raid: {:?}", e))?;
+
+  let _ = twitch.send_chat_message(format!(
+    "We're raiding {}! Copy the raid message:",
+    user.name
+  ));
+  let _ = twitch.send_chat_message(templates::render(
+    &config.raid_message,
+    &[("target", &user.name)],
+  ));
+  Ok(format!(
+    "Raiding {}, it goes through in 90 seconds. Use \"raid cancel\" to stop it.",
+    user.name
+  ))
+}
+
+struct QueuedShoutout {
+  broadcaster_id: String,
+  name: String,
+  attempts: u32,
+}
+
+// Sends shoutouts through the official endpoint one at a time, callers pass
+// in the current instant so the clock can be faked.
+#[derive(Default)]
+pub struct ShoutoutQueue {
+  queued: VecDeque<QueuedShoutout>,
+  last_sent: Option<Instant>,
+  sent: HashMap<String, Instant>,
+}
+
+impl ShoutoutQueue {
+  pub fn push(&mut self, broadcaster_id: &str, name: &str) {
+    if self
+      .queued
+      .iter()
+      .any(|queued| queued.broadcaster_id == broadcaster_id)
+    {
+      return;
+    }
+
+    self.queued.push_back(QueuedShoutout {
+      broadcaster_id: broadcaster_id.to_owned(),
+      name: name.to_owned(),
+      attempts: 0,
+    });
+  }
+
+  pub fn tick(&mut self, twitch: &TwitchEventSubApi, now: Instant) {
+    let Some(mut shoutout) = self.next(now) else {
+      return;
+    };
+
+    match helix::send_shoutout(twitch, &shoutout.broadcaster_id) {
+      Ok(_) => {
+        println!("Shouted out {}", shoutout.name);
+        self.sent.insert(shoutout.broadcaster_id, now);
+      }
+      Err(e) => {
+        shoutout.attempts += 1;
+        error!("Failed to shout out {}: {:?}", shoutout.name, e);
+        if shoutout.attempts < SHOUTOUT_ATTEMPTS {
+          self.queued.push_front(shoutout);
+        } else {
+          println!("Gave up shouting out {}", shoutout.name);
+        }
+      }
+    }
+  }
+
+  // Takes the next shoutout once both cooldowns allow it
+  fn next(&mut self, now: Instant) -> Option<QueuedShoutout> {
+    if self
+      .last_sent
+      .is_some_and(|sent| now.duration_since(sent) < SHOUTOUT_COOLDOWN)
+    {
+      return None;
+    }
+
+    let shoutout = self.queued.pop_front()?;
+    if let Some(sent) = self.sent.get(&shoutout.broadcaster_id) {
+      if now.duration_since(*sent) < SAME_CHANNEL_COOLDOWN {
+        println!(
+          "{} was shouted out less than an hour ago, skipping the shoutout",
+          shoutout.name
+        );
+        return None;
+      }
+    }
+
+    self.last_sent = Some(now);
+    Some(shoutout)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn next_name(shoutouts: &mut ShoutoutQueue, now: Instant) -> Option<String> {
+    shoutouts.next(now).map(|shoutout| shoutout.name)
+  }
+
+  #[test]
+  fn shoutouts_wait_for_the_cooldown() {
+    let start = Instant::now();
+    let mut shoutouts = ShoutoutQueue::default();
+    shoutouts.push("1", "owl");
+    shoutouts.push("2", "kal");
+    shoutouts.push("1", "owl");
+
+    assert_eq!(next_name(&mut shoutouts, start).as_deref(), Some("owl"));
+    assert_eq!(
+      next_name(&mut shoutouts, start + SHOUTOUT_COOLDOWN / 2),
+      None
+    );
+    assert_eq!(
+      next_name(&mut shoutouts, start + SHOUTOUT_COOLDOWN).as_deref(),
+      Some("kal")
+    );
+    assert_eq!(
+      next_name(&mut shoutouts, start + SHOUTOUT_COOLDOWN * 2),
+      None
+    );
+  }
+
+  #[test]
+  fn same_channel_is_skipped_within_the_hour() {
+    let start = Instant::now();
+    let mut shoutouts = ShoutoutQueue::default();
+    shoutouts.sent.insert("1".to_owned(), start);
+
+    shoutouts.push("1", "owl");
+    assert_eq!(next_name(&mut shoutouts, start + SHOUTOUT_COOLDOWN), None);
+
+    shoutouts.push("1", "owl");
+    assert_eq!(
+      next_name(&mut shoutouts, start + SAME_CHANNEL_COOLDOWN).as_deref(),
+      Some("owl")
+    );
+  }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::{error, CustomPointsRewardRedeemData, TwitchEventSubApi};
+
+use crate::modules::helix::{self, Redemption, RedemptionStatus};
+
+#[derive(Serialise, Deserialise, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedemptionPolicy {
+  // Fulfilled when the redeem worked, refunded when it didn't
+  Automatic,
+  // Fulfilled even when the redeem failed, nothing is refunded
+  AlwaysFulfil,
+  // Left in the Twitch queue for the streamer to tick off
+  Manual,
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+pub struct RewardPolicy {
+  // Reward id, or its exact title
+  pub reward: String,
+  pub policy: RedemptionPolicy,
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct RedemptionConfig {
+  pub default_policy: RedemptionPolicy,
+  pub rewards: Vec<RewardPolicy>,
+}
+
+impl Default for RedemptionConfig {
+  fn default() -> Self {
+    RedemptionConfig {
+      default_policy: RedemptionPolicy::Automatic,
+      rewards: Vec::new(),
+    }
+  }
+}
+
+impl RedemptionConfig {
+  pub fn policy_for(&self, reward_id: &str, title: &str) -> RedemptionPolicy {
+    self
+      .rewards
+      .iter()
+      .find(|reward| reward.reward == reward_id || reward.reward == title)
+      .map(|reward| reward.policy)
+      .unwrap_or(self.default_policy)
+  }
+}
+
+pub enum RedeemOutcome {
+  Succeeded,
+  // Explains to the viewer why it didn't work
+  Failed(String),
+  // Resolved later, or nothing handled the reward
+  Pending,
+}
+
+#[derive(Clone, Debug)]
+pub struct PointsRedemption {
+  pub redemption: Redemption,
+  pub username: String,
+  policy: RedemptionPolicy,
+}
+
+impl PointsRedemption {
+  // Twitch only lets the client that created a reward change its
+  // redemptions, so the rest are always left to the streamer
+  pub fn new(
+    config: &RedemptionConfig,
+    reward: &CustomPointsRewardRedeemData,
+    managed: bool,
+  ) -> PointsRedemption {
+    let policy = if managed {
+      config.policy_for(&reward.reward.id, &reward.reward.title)
+    } else {
+      RedemptionPolicy::Manual
+    };
+
+    PointsRedemption {
+      redemption: Redemption {
+        reward_id: reward.reward.id.to_owned(),
+        id: reward.id.to_owned(),
+      },
+      username: reward.user.name.to_owned(),
+      policy,
+    }
+  }
+
+  fn update_status(&self, twitch: &TwitchEventSubApi, status: RedemptionStatus) -> bool {
+    match helix::update_redemption_status(twitch, &self.redemption, status) {
+      Ok(_) => true,
+      Err(e) => {
+        error!(
+          "Failed to mark {}'s redemption {:?}: {:?}",
+          self.username, status, e
+        );
+        false
+      }
+    }
+  }
+
+  pub fn resolve(&self, twitch: &mut TwitchEventSubApi, outcome: RedeemOutcome) {
+    match (outcome, self.policy) {
+      (RedeemOutcome::Pending, _) | (RedeemOutcome::Succeeded, RedemptionPolicy::Manual) => {}
+      (RedeemOutcome::Succeeded, _) => {
+        self.update_status(twitch, RedemptionStatus::Fulfilled);
+      }
+      (RedeemOutcome::Failed(reason), policy) => {
+        let failed_text = if policy == RedemptionPolicy::Automatic
+          && self.update_status(twitch, RedemptionStatus::Canceled)
+        {
+          format!(
+            "{}, {} so your points have been refunded.",
+            self.username, reason
+          )
+        } else {
+          if policy == RedemptionPolicy::AlwaysFulfil {
+            self.update_status(twitch, RedemptionStatus::Fulfilled);
+          }
+          format!("{}, {}.", self.username, reason)
+        };
+        let _ = twitch.send_chat_message(failed_text.to_owned());
+        println!("{}", failed_text);
+      }
+    }
+  }
+}
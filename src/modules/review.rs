@@ -0,0 +1,67 @@
+use crate::{modules::moderation::FlaggedMessage, ChatMessage};
+
+// Oldest held messages are dropped once the queue grows past this
+const REVIEW_QUEUE_LIMIT: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReviewDecision {
+  Approve,
+  Delete,
+  Ban,
+}
+
+pub struct HeldMessage {
+  pub id: u32,
+  pub flagged: FlaggedMessage,
+  pub chat_message: ChatMessage,
+}
+
+#[derive(Default)]
+pub struct ReviewQueue {
+  next_id: u32,
+  held: Vec<HeldMessage>,
+}
+
+impl ReviewQueue {
+  pub fn hold(&mut self, flagged: FlaggedMessage, chat_message: ChatMessage) -> u32 {
+    self.next_id += 1;
+    let id = self.next_id;
+    println!(
+      "Held #{} from {} for review (score {}: {}): {}",
+      id,
+      flagged.username,
+      flagged.spam_check.score,
+      flagged.spam_check.matched_rules.join(", "),
+      flagged.text
+    );
+    println!("    a {} approve | d {} delete | b {} ban", id, id, id);
+
+    self.held.push(HeldMessage {
+      id,
+      flagged,
+      chat_message,
+    });
+    if self.held.len() > REVIEW_QUEUE_LIMIT {
+      self.held.remove(0);
+    }
+
+    id
+  }
+
+  pub fn take(&mut self, id: u32) -> Option<HeldMessage> {
+    let idx = self.held.iter().position(|held| held.id == id)?;
+    Some(self.held.remove(idx))
+  }
+
+  pub fn print(&self) {
+    if self.held.is_empty() {
+      println!("No messages are waiting for review.");
+    }
+    for held in &self.held {
+      println!(
+        "#{} {} (score {}): {}",
+        held.id, held.flagged.username, held.flagged.spam_check.score, held.flagged.text
+      );
+    }
+  }
+}
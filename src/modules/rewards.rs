@@ -0,0 +1,433 @@
+use std::fs;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::{error, CreateCustomReward, TwitchEventSubApi, UpdateCustomReward};
+
+use crate::modules::{consts::REWARDS_FILE, templates};
+
+// Text and arguments can use {user} and {input} from the redemption
+#[derive(Serialise, Deserialise, Clone, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RewardAction {
+  Print { text: String },
+  AddPoints { points: u32 },
+  RankUp,
+  RankDown,
+  Tts,
+  RunCommand { program: String, args: Vec<String> },
+  PlaySound { player: String, path: String },
+  IncrementCounter { counter: String },
+  // Lasts for a while and is announced in chat when it wears off
+  StartEffect { name: String, seconds: u64 },
+}
+
+impl RewardAction {
+  // Commands and sounds run in the background, only starting them can fail
+  pub fn spawn(&self, user: &str, input: &str) -> io::Result<()> {
+    let (program, args) = match self {
+      RewardAction::RunCommand { program, args } => (program, args.to_owned()),
+      RewardAction::PlaySound { player, path } => (player, vec![path.to_owned()]),
+      _ => return Ok(()),
+    };
+
+    Command::new(program)
+      .args(args.iter().map(|arg| fill_template(arg, user, input)))
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn()
+      .map(|_| ())
+  }
+}
+
+pub fn fill_template(text: &str, user: &str, input: &str) -> String {
+  templates::render(text, &[("user", user), ("input", input)])
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+pub struct RewardDefinition {
+  // Filled in when the reward is created or first redeemed
+  #[serde(default)]
+  pub id: Option<String>,
+  pub title: String,
+  #[serde(default)]
+  pub prompt: String,
+  pub cost: i64,
+  #[serde(default)]
+  pub user_input_required: bool,
+  // Twitch only lets the client that created a reward change it
+  #[serde(default)]
+  pub managed: bool,
+  pub actions: Vec<RewardAction>,
+}
+
+impl RewardDefinition {
+  fn print(text: &str) -> RewardAction {
+    RewardAction::Print {
+      text: text.to_owned(),
+    }
+  }
+
+  // How rewards were handled before the registry, by keywords in their title
+  fn legacy_actions(title: &str) -> Vec<RewardAction> {
+    let mut actions = Vec::new();
+    if title.contains("water") {
+      actions.push(RewardDefinition::print("{user} watered the Owl!"));
+    }
+    if title.contains("EU") {
+      actions.push(RewardDefinition::print("{user} has sent Owl to the EU!"));
+      actions.push(RewardAction::StartEffect {
+        name: "EU mode".to_owned(),
+        seconds: 30 * 60,
+      });
+    }
+    if title.contains("editor") {
+      actions.push(RewardDefinition::print(
+        "{user} has requested Owl uses {input}",
+      ));
+      actions.push(RewardAction::StartEffect {
+        name: "Editor request".to_owned(),
+        seconds: 30 * 60,
+      });
+    }
+    if title.contains("Video") {
+      actions.push(RewardDefinition::print("{user}: Redeemed Video"));
+    }
+    if title.contains("game") {
+      actions.push(RewardDefinition::print(
+        "{user} has request Owl to live a rust free life.",
+      ));
+      actions.push(RewardAction::StartEffect {
+        name: "Rust free life".to_owned(),
+        seconds: 30 * 60,
+      });
+    }
+    if title.contains("Feed the fimsh! x10") {
+      actions.push(RewardDefinition::print("{user} fed the fimsh 10x times!"));
+      actions.push(RewardAction::AddPoints { points: 10 });
+    } else if title.contains("Feed the fimsh") {
+      actions.push(RewardDefinition::print("{user} fed the fimsh!"));
+      actions.push(RewardAction::AddPoints { points: 1 });
+    }
+    if title.contains("TTS") {
+      actions.push(RewardAction::Tts);
+    }
+    if title.contains("RankUp") {
+      actions.push(RewardAction::RankUp);
+    }
+    if title.contains("RankDown") {
+      actions.push(RewardAction::RankDown);
+    }
+
+    actions
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RewardToggle {
+  Pause,
+  Resume,
+  Enable,
+  Disable,
+}
+
+impl RewardToggle {
+  pub fn parse(toggle: &str) -> Option<RewardToggle> {
+    match toggle {
+      "pause" => Some(RewardToggle::Pause),
+      "resume" => Some(RewardToggle::Resume),
+      "enable" => Some(RewardToggle::Enable),
+      "disable" => Some(RewardToggle::Disable),
+      _ => None,
+    }
+  }
+
+  fn update(&self) -> UpdateCustomReward {
+    let mut update = UpdateCustomReward::new();
+    match self {
+      RewardToggle::Pause => update.is_paused = Some(true),
+      RewardToggle::Resume => update.is_paused = Some(false),
+      RewardToggle::Enable => update = update.enable(),
+      RewardToggle::Disable => update = update.disable(),
+    }
+    update
+  }
+
+  fn past_tense(&self) -> &'static str {
+    match self {
+      RewardToggle::Pause => "paused",
+      RewardToggle::Resume => "resumed",
+      RewardToggle::Enable => "enabled",
+      RewardToggle::Disable => "disabled",
+    }
+  }
+}
+
+// Finds the reward by its exact title, or by part of it when only one matches
+pub fn toggle_reward(
+  twitch: &mut TwitchEventSubApi,
+  registry: &RewardRegistry,
+  name: &str,
+  toggle: RewardToggle,
+) -> Result<String, String> {
+  let rewards = twitch
+    .get_custom_rewards()
+    .map_err(|e| format!("Couldn't get the rewards: {:?}", e))?
+    .data;
+
+  let name = name.to_lowercase();
+  let matching = match rewards
+    .iter()
+    .find(|reward| reward.title.to_lowercase() == name)
+  {
+    Some(reward) => vec![reward],
+    None => rewards
+      .iter()
+      .filter(|reward| reward.title.to_lowercase().contains(&name))
+      .collect(),
+  };
+
+  let [reward] = matching[..] else {
+    return Err(format!(
+      "{} rewards match \"{}\", be more specific.",
+      matching.len(),
+      name
+    ));
+  };
+
+  // Twitch only lets the client that created a reward change it
+  if !registry
+    .rewards
+    .iter()
+    .any(|registered| registered.managed && registered.id.as_deref() == Some(reward.id.as_str()))
+  {
+    return Err(format!(
+      "{} is not managed by Owlbot, delete it on Twitch and re-create it with sync.",
+      reward.title
+    ));
+  }
+
+  twitch
+    .update_custom_reward(&reward.id, toggle.update())
+    .map_err(|e| format!("Couldn't update {}: {:?}", reward.title, e))?;
+  Ok(format!(
+    "{} has been {}.",
+    reward.title,
+    toggle.past_tense()
+  ))
+}
+
+#[derive(Serialise, Deserialise, Default)]
+pub struct RewardRegistry {
+  pub rewards: Vec<RewardDefinition>,
+  // Rewards files imported before effects existed get them added once
+  #[serde(default)]
+  legacy_effects_added: bool,
+}
+
+impl RewardRegistry {
+  pub fn load() -> RewardRegistry {
+    let mut registry = RewardRegistry::default();
+    if let Ok(rewards_string) = fs::read_to_string(REWARDS_FILE) {
+      registry = serde_json::from_str(&rewards_string).unwrap();
+    }
+
+    registry
+  }
+
+  pub fn save(&self) {
+    let mut file = fs::File::create(REWARDS_FILE).unwrap();
+    if let Ok(rewards) = serde_json::to_string_pretty(self) {
+      file.write_all(rewards.as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+  }
+
+  // Matches by id, then by exact title, remembering the id for next time
+  pub fn find(&mut self, reward_id: &str, title: &str) -> Option<&RewardDefinition> {
+    let idx = match self
+      .rewards
+      .iter()
+      .position(|reward| reward.id.as_deref() == Some(reward_id))
+    {
+      Some(idx) => idx,
+      None => {
+        let idx = self
+          .rewards
+          .iter()
+          .position(|reward| reward.id.is_none() && reward.title == title)?;
+        self.rewards[idx].id = Some(reward_id.to_owned());
+        self.save();
+        idx
+      }
+    };
+
+    self.rewards.get(idx)
+  }
+
+  // Builds the registry from the channel's current rewards the first time
+  // the bot runs without a rewards file
+  pub fn import_existing(&mut self, twitch: &mut TwitchEventSubApi) {
+    let existing = match twitch.get_custom_rewards() {
+      Ok(rewards) => rewards.data,
+      Err(e) => {
+        error!("Failed to get custom rewards: {:?}", e);
+        return;
+      }
+    };
+
+    for reward in existing {
+      self.rewards.push(RewardDefinition {
+        id: Some(reward.id),
+        actions: RewardDefinition::legacy_actions(&reward.title),
+        title: reward.title,
+        prompt: reward.prompt,
+        cost: reward.cost as i64,
+        user_input_required: reward.is_user_input_required,
+        managed: false,
+      });
+    }
+    println!(
+      "Imported {} rewards into {}",
+      self.rewards.len(),
+      REWARDS_FILE
+    );
+    self.save();
+  }
+
+  // Gives imported rewards the effects their legacy handling had, removing
+  // one from the rewards file afterwards keeps it removed
+  pub fn add_legacy_effects(&mut self) {
+    if self.legacy_effects_added {
+      return;
+    }
+
+    for reward in &mut self.rewards {
+      for effect in RewardDefinition::legacy_actions(&reward.title) {
+        let RewardAction::StartEffect { name, .. } = &effect else {
+          continue;
+        };
+        let has_effect = reward.actions.iter().any(|action| {
+          matches!(action, RewardAction::StartEffect { name: existing, .. } if existing == name)
+        });
+        if !has_effect {
+          println!("Added the {} effect to {}", name, reward.title);
+          reward.actions.push(effect);
+        }
+      }
+    }
+
+    self.legacy_effects_added = true;
+    self.save();
+  }
+
+  pub fn list(&self, twitch: &mut TwitchEventSubApi) {
+    let existing = match twitch.get_custom_rewards() {
+      Ok(rewards) => rewards.data,
+      Err(e) => {
+        println!("Couldn't get the rewards: {:?}", e);
+        return;
+      }
+    };
+
+    for reward in &self.rewards {
+      let state = match existing
+        .iter()
+        .find(|existing| reward.id.as_deref() == Some(existing.id.as_str()))
+      {
+        Some(existing) if !existing.is_enabled => "disabled",
+        Some(existing) if existing.is_paused => "paused",
+        Some(_) => "live",
+        None => "missing on Twitch, run sync",
+      };
+      println!(
+        "{} ({}P) [{}]{}",
+        reward.title,
+        reward.cost,
+        state,
+        if reward.managed {
+          ""
+        } else {
+          " not managed by Owlbot"
+        }
+      );
+    }
+
+    for existing in existing.iter().filter(|existing| {
+      !self
+        .rewards
+        .iter()
+        .any(|reward| reward.id.as_deref() == Some(existing.id.as_str()))
+    }) {
+      println!(
+        "{} ({}P) [not in {}]",
+        existing.title, existing.cost, REWARDS_FILE
+      );
+    }
+  }
+
+  // Creates rewards that don't exist yet and updates the ones the bot manages
+  pub fn sync(&mut self, twitch: &mut TwitchEventSubApi) {
+    let existing = match twitch.get_custom_rewards() {
+      Ok(rewards) => rewards.data,
+      Err(e) => {
+        error!("Failed to get custom rewards: {:?}", e);
+        return;
+      }
+    };
+
+    for reward in &mut self.rewards {
+      // Deleted on Twitch since it was last synced
+      if reward
+        .id
+        .as_ref()
+        .is_some_and(|id| !existing.iter().any(|existing| existing.id == *id))
+      {
+        println!("{} no longer exists on Twitch", reward.title);
+        reward.id = None;
+        reward.managed = false;
+      }
+
+      if reward.id.is_none() {
+        reward.id = existing
+          .iter()
+          .find(|existing| existing.title == reward.title)
+          .map(|existing| existing.id.to_owned());
+      }
+
+      match &reward.id {
+        None => {
+          let created = twitch.create_custom_reward(CreateCustomReward {
+            title: reward.title.to_owned(),
+            prompt: reward.prompt.to_owned(),
+            cost: reward.cost,
+            is_user_input_required: reward.user_input_required,
+            ..Default::default()
+          });
+          match created {
+            Ok(created) => {
+              reward.id = created.data.first().map(|created| created.id.to_owned());
+              reward.managed = true;
+              println!("Created reward {}", reward.title);
+            }
+            Err(e) => error!("Failed to create reward {}: {:?}", reward.title, e),
+          }
+        }
+        Some(id) if reward.managed => {
+          let mut update = UpdateCustomReward::new()
+            .title(reward.title.to_owned())
+            .prompt(reward.prompt.to_owned());
+          update.cost = Some(reward.cost);
+          update.is_user_input_required = Some(reward.user_input_required);
+          if let Err(e) = twitch.update_custom_reward(id, update) {
+            error!("Failed to update reward {}: {:?}", reward.title, e);
+          }
+        }
+        Some(_) => {}
+      }
+    }
+
+    self.save();
+  }
+}
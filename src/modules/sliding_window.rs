@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Counts events within the last `window`, callers pass in the current
+// instant so the clock can be faked.
+pub struct SlidingWindow {
+  window: Duration,
+  events: VecDeque<Instant>,
+}
+
+impl SlidingWindow {
+  pub fn new(window: Duration) -> SlidingWindow {
+    SlidingWindow {
+      window,
+      events: VecDeque::new(),
+    }
+  }
+
+  fn expire(&mut self, now: Instant) {
+    while let Some(oldest) = self.events.front() {
+      if now.saturating_duration_since(*oldest) < self.window {
+        break;
+      }
+      self.events.pop_front();
+    }
+  }
+
+  pub fn record(&mut self, now: Instant) -> usize {
+    self.events.push_back(now);
+    self.count(now)
+  }
+
+  pub fn count(&mut self, now: Instant) -> usize {
+    self.expire(now);
+    self.events.len()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn counts_events_inside_the_window() {
+    let start = Instant::now();
+    let mut window = SlidingWindow::new(Duration::from_secs(10));
+
+    assert_eq!(window.record(start), 1);
+    assert_eq!(window.record(start + Duration::from_secs(4)), 2);
+    assert_eq!(window.record(start + Duration::from_secs(9)), 3);
+  }
+
+  #[test]
+  fn old_events_expire() {
+    let start = Instant::now();
+    let mut window = SlidingWindow::new(Duration::from_secs(10));
+    window.record(start);
+    window.record(start + Duration::from_secs(5));
+
+    assert_eq!(window.count(start + Duration::from_secs(10)), 1);
+    assert_eq!(window.count(start + Duration::from_secs(15)), 0);
+  }
+}
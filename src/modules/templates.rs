@@ -0,0 +1,20 @@
+// Replaces each {name} in the template with its value, unknown names are
+// left as they are so mistakes show up in chat rather than vanishing
+pub fn render(template: &str, values: &[(&str, &str)]) -> String {
+  let mut rendered = template.to_owned();
+  for (name, value) in values {
+    rendered = rendered.replace(&format!("{{{}}}", name), value);
+  }
+
+  rendered
+}
+
+// Twitch sends tiers as 1000, 2000 and 3000
+pub fn tier_number(tier: &str) -> &str {
+  match tier {
+    "1000" => "1",
+    "2000" => "2",
+    "3000" => "3",
+    tier => tier,
+  }
+}
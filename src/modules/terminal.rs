@@ -0,0 +1,83 @@
+use std::io::stdin;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+
+use crate::modules::{raids::RaidCommand, review::ReviewDecision, tts_queue::TtsControl};
+
+pub enum TerminalCommand {
+  Review(ReviewDecision, u32),
+  ShowReviewQueue,
+  ShowPermits,
+  ShowEffects,
+  ShowHypeTrain,
+  Tts(TtsControl),
+  Raid(RaidCommand),
+  Help,
+}
+
+impl TerminalCommand {
+  fn parse(line: &str) -> Option<TerminalCommand> {
+    match line.split_whitespace().collect::<Vec<_>>()[..] {
+      ["raid", ref rest @ ..] => Some(TerminalCommand::Raid(RaidCommand::parse(rest)?)),
+      ["a", id] => Some(TerminalCommand::Review(
+        ReviewDecision::Approve,
+        id.parse().ok()?,
+      )),
+      ["d", id] => Some(TerminalCommand::Review(
+        ReviewDecision::Delete,
+        id.parse().ok()?,
+      )),
+      ["b", id] => Some(TerminalCommand::Review(
+        ReviewDecision::Ban,
+        id.parse().ok()?,
+      )),
+      ["q"] => Some(TerminalCommand::ShowReviewQueue),
+      ["p"] => Some(TerminalCommand::ShowPermits),
+      ["e"] => Some(TerminalCommand::ShowEffects),
+      ["h"] => Some(TerminalCommand::ShowHypeTrain),
+      ["s"] => Some(TerminalCommand::Tts(TtsControl::Skip)),
+      ["z"] => Some(TerminalCommand::Tts(TtsControl::Pause)),
+      ["r"] => Some(TerminalCommand::Tts(TtsControl::Resume)),
+      ["c"] => Some(TerminalCommand::Tts(TtsControl::Clear)),
+      ["t"] => Some(TerminalCommand::Tts(TtsControl::Show)),
+      ["y", id] => Some(TerminalCommand::Tts(TtsControl::Approve(id.parse().ok()?))),
+      ["n", id] => Some(TerminalCommand::Tts(TtsControl::Reject(id.parse().ok()?))),
+      [] => None,
+      _ => Some(TerminalCommand::Help),
+    }
+  }
+
+  pub fn help() -> &'static str {
+    "Keys (then enter): a <id> approve | d <id> delete | b <id> ban | q review queue | p link permits | e effects | h hype train\n\
+     TTS: s skip | z pause | r resume | c clear | t queue | y <id> approve | n <id> reject\n\
+     Raids: raid suggest targets | raid <channel> start | raid cancel"
+  }
+}
+
+// Stdin is read on its own thread so the bot loop never blocks on it
+pub struct TerminalInput {
+  lines: Receiver<String>,
+}
+
+impl TerminalInput {
+  pub fn spawn() -> TerminalInput {
+    let (sender, lines) = channel();
+    thread::spawn(move || {
+      for line in stdin().lines().map_while(Result::ok) {
+        if sender.send(line).is_err() {
+          break;
+        }
+      }
+    });
+
+    TerminalInput { lines }
+  }
+
+  pub fn commands(&self) -> Vec<TerminalCommand> {
+    self
+      .lines
+      .try_iter()
+      .filter_map(|line| TerminalCommand::parse(&line))
+      .collect()
+  }
+}
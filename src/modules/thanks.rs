@@ -0,0 +1,173 @@
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::TwitchEventSubApi;
+
+use crate::modules::templates;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ThanksKind {
+  Subscription,
+  Resubscription,
+  Gift,
+  Cheer,
+  Raid,
+  ReturningRaid,
+}
+
+// Templates can use {user}, {amount} and {tier}, where amount is the months,
+// subs gifted, bits or raiders depending on the event. Raids can also use
+// {game} and {title} from the raider's stream and {raids} for how many
+// times they have raided.
+#[derive(Serialise, Deserialise, Clone)]
+pub struct ThankYou {
+  #[serde(default)]
+  pub min: u32,
+  // Twitch's tier, 1000, 2000 or 3000, any tier when missing
+  #[serde(default)]
+  pub tier: Option<String>,
+  pub message: String,
+}
+
+impl ThankYou {
+  fn new(min: u32, tier: Option<&str>, message: &str) -> ThankYou {
+    ThankYou {
+      min,
+      tier: tier.map(|tier| tier.to_owned()),
+      message: message.to_owned(),
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct ThanksConfig {
+  pub subscription: Vec<ThankYou>,
+  pub resubscription: Vec<ThankYou>,
+  pub gift: Vec<ThankYou>,
+  pub cheer: Vec<ThankYou>,
+  pub raid: Vec<ThankYou>,
+  pub returning_raid: Vec<ThankYou>,
+}
+
+impl Default for ThanksConfig {
+  fn default() -> Self {
+    ThanksConfig {
+      subscription: vec![
+        ThankYou::new(
+          0,
+          None,
+          "Thank you for subscribing {user}, welcome to the parliament!",
+        ),
+        ThankYou::new(
+          0,
+          Some("3000"),
+          "A tier 3 sub?! {user} you are far too kind, thank you!",
+        ),
+      ],
+      resubscription: vec![
+        ThankYou::new(0, None, "Thank you for resubscribing {user}!"),
+        ThankYou::new(
+          12,
+          None,
+          "{amount} months! Thank you for sticking around {user}!",
+        ),
+      ],
+      gift: vec![
+        ThankYou::new(1, None, "Thank you for the gift sub {user}!"),
+        ThankYou::new(
+          5,
+          None,
+          "{user} just gifted {amount} subs! Thank you so much!",
+        ),
+      ],
+      cheer: vec![
+        ThankYou::new(1, None, "Thanks for the {amount} bits {user}!"),
+        ThankYou::new(
+          100,
+          None,
+          "{amount} bits! {user} has fed the fimsh for a week, thank you!",
+        ),
+      ],
+      raid: vec![ThankYou::new(
+        1,
+        None,
+        "Thank you for the raid {user}! Welcome in raiders, hope you enjoyed the {game}!",
+      )],
+      returning_raid: vec![ThankYou::new(
+        1,
+        None,
+        "Welcome back {user}! That's raid number {raids}, thank you for bringing {amount} friends over from {game}!",
+      )],
+    }
+  }
+}
+
+impl ThanksConfig {
+  fn templates(&self, kind: ThanksKind) -> &[ThankYou] {
+    match kind {
+      ThanksKind::Subscription => &self.subscription,
+      ThanksKind::Resubscription => &self.resubscription,
+      ThanksKind::Gift => &self.gift,
+      ThanksKind::Cheer => &self.cheer,
+      ThanksKind::Raid => &self.raid,
+      ThanksKind::ReturningRaid => &self.returning_raid,
+    }
+  }
+
+  // The highest threshold that was reached wins, with a matching tier
+  // beating a template for any tier
+  pub fn message(
+    &self,
+    kind: ThanksKind,
+    user: &str,
+    amount: u32,
+    tier: &str,
+    extra: &[(&str, &str)],
+  ) -> Option<String> {
+    let thank_you = self
+      .templates(kind)
+      .iter()
+      .filter(|thank_you| amount >= thank_you.min)
+      .filter(|thank_you| {
+        thank_you
+          .tier
+          .as_deref()
+          .is_none_or(|wanted| wanted == tier)
+      })
+      .max_by_key(|thank_you| (thank_you.min, thank_you.tier.is_some()))?;
+
+    let amount = amount.to_string();
+    let mut values = vec![
+      ("user", user),
+      ("amount", amount.as_str()),
+      ("tier", templates::tier_number(tier)),
+    ];
+    values.extend_from_slice(extra);
+    Some(templates::render(&thank_you.message, &values))
+  }
+
+  pub fn thank(
+    &self,
+    twitch: &mut TwitchEventSubApi,
+    kind: ThanksKind,
+    user: &str,
+    amount: u32,
+    tier: &str,
+  ) {
+    self.thank_with(twitch, kind, user, amount, tier, &[]);
+  }
+
+  // For events that have more to say than the usual values
+  pub fn thank_with(
+    &self,
+    twitch: &mut TwitchEventSubApi,
+    kind: ThanksKind,
+    user: &str,
+    amount: u32,
+    tier: &str,
+    extra: &[(&str, &str)],
+  ) {
+    if let Some(thank_you) = self.message(kind, user, amount, tier, extra) {
+      let _ = twitch.send_chat_message(thank_you);
+    }
+  }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+
+use crate::modules::{clock::unix_timestamp, consts::SPEECH_FILE, tts_filter::TtsFilterConfig};
+
+#[derive(Debug)]
+pub enum TtsError {
+  NotInstalled(String),
+  Io(io::Error),
+  Failed { program: String, stderr: String },
+}
+
+impl fmt::Display for TtsError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TtsError::NotInstalled(program) => write!(f, "{} is not installed", program),
+      TtsError::Io(e) => write!(f, "{}", e),
+      TtsError::Failed { program, stderr } => write!(f, "{} failed: {}", program, stderr.trim()),
+    }
+  }
+}
+
+impl From<io::Error> for TtsError {
+  fn from(e: io::Error) -> Self {
+    TtsError::Io(e)
+  }
+}
+
+// How a backend is speaking the text it was given
+pub enum Playback {
+  // Still playing until the process exits
+  Process(Child),
+  // Another program is reading it and can't be waited on
+  Detached,
+  Finished,
+}
+
+pub trait TtsBackend {
+  fn name(&self) -> &str;
+  // May block while the speech is prepared, but not while it plays
+  fn speak(&self, text: &str) -> Result<Playback, TtsError>;
+  // Only needed for backends that play detached
+  fn stop(&self) -> Result<(), TtsError> {
+    Ok(())
+  }
+}
+
+fn spawn(program: &str, args: &[&str], input: Option<&str>) -> Result<Child, TtsError> {
+  let child = Command::new(program)
+    .args(args)
+    .stdin(if input.is_some() {
+      Stdio::piped()
+    } else {
+      Stdio::null()
+    })
+    .stdout(Stdio::null())
+    .stderr(Stdio::piped())
+    .spawn();
+
+  let mut child = match child {
+    Ok(child) => child,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+      return Err(TtsError::NotInstalled(program.to_owned()))
+    }
+    Err(e) => return Err(e.into()),
+  };
+
+  // Dropping stdin closes it so the program knows the input is complete
+  if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+    stdin.write_all(input.as_bytes())?;
+  }
+
+  Ok(child)
+}
+
+fn run(program: &str, args: &[&str], input: Option<&str>) -> Result<(), TtsError> {
+  let output = spawn(program, args, input)?.wait_with_output()?;
+  if !output.status.success() {
+    return Err(TtsError::Failed {
+      program: program.to_owned(),
+      stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    });
+  }
+
+  Ok(())
+}
+
+pub struct DsNote;
+
+impl TtsBackend for DsNote {
+  fn name(&self) -> &str {
+    "dsnote"
+  }
+
+  fn speak(&self, text: &str) -> Result<Playback, TtsError> {
+    let mut file = fs::File::create(SPEECH_FILE)?;
+    file.write_all(format!("{}\n", text).as_bytes())?;
+    file.flush()?;
+
+    run("dsnote", &[&format!("./{}", SPEECH_FILE)], None)?;
+    // dsnote needs a moment to load the file before it can read it
+    thread::sleep(Duration::from_millis(1000));
+    run("dsnote", &["--action", "start-reading"], None)?;
+    Ok(Playback::Detached)
+  }
+
+  fn stop(&self) -> Result<(), TtsError> {
+    run("dsnote", &["--action", "stop-reading"], None)
+  }
+}
+
+pub struct Piper {
+  pub model: String,
+  pub player: String,
+}
+
+impl TtsBackend for Piper {
+  fn name(&self) -> &str {
+    "piper"
+  }
+
+  fn speak(&self, text: &str) -> Result<Playback, TtsError> {
+    let wav = format!("{}.wav", SPEECH_FILE);
+    run(
+      "piper",
+      &["--model", &self.model, "--output_file", &wav],
+      Some(text),
+    )?;
+    Ok(Playback::Process(spawn(&self.player, &[&wav], None)?))
+  }
+}
+
+pub struct EspeakNg {
+  pub voice: String,
+}
+
+impl TtsBackend for EspeakNg {
+  fn name(&self) -> &str {
+    "espeak-ng"
+  }
+
+  fn speak(&self, text: &str) -> Result<Playback, TtsError> {
+    Ok(Playback::Process(spawn(
+      "espeak-ng",
+      &["-v", &self.voice, "--stdin"],
+      Some(text),
+    )?))
+  }
+}
+
+// Writes each message to its own text file instead of speaking it, handy
+// for testing on a machine without any voices installed
+pub struct Directory {
+  pub path: PathBuf,
+}
+
+impl TtsBackend for Directory {
+  fn name(&self) -> &str {
+    "directory"
+  }
+
+  fn speak(&self, text: &str) -> Result<Playback, TtsError> {
+    fs::create_dir_all(&self.path)?;
+    let mut count = 0;
+    let mut file_path = self.path.join(format!("{}.txt", unix_timestamp()));
+    while file_path.exists() {
+      count += 1;
+      file_path = self
+        .path
+        .join(format!("{}-{}.txt", unix_timestamp(), count));
+    }
+
+    let mut file = fs::File::create(file_path)?;
+    file.write_all(format!("{}\n", text).as_bytes())?;
+    file.flush()?;
+    Ok(Playback::Finished)
+  }
+}
+
+#[derive(Serialise, Deserialise, Clone, Default)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum TtsBackendConfig {
+  #[default]
+  Dsnote,
+  Piper {
+    model: String,
+    player: String,
+  },
+  EspeakNg {
+    voice: String,
+  },
+  Directory {
+    path: PathBuf,
+  },
+}
+
+impl TtsBackendConfig {
+  pub fn backend(&self) -> Arc<dyn TtsBackend + Send + Sync> {
+    match self {
+      TtsBackendConfig::Dsnote => Arc::new(DsNote),
+      TtsBackendConfig::Piper { model, player } => Arc::new(Piper {
+        model: model.to_owned(),
+        player: player.to_owned(),
+      }),
+      TtsBackendConfig::EspeakNg { voice } => Arc::new(EspeakNg {
+        voice: voice.to_owned(),
+      }),
+      TtsBackendConfig::Directory { path } => Arc::new(Directory { path: path.clone() }),
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+pub struct TtsVoice {
+  pub name: String,
+  #[serde(flatten)]
+  pub backend: TtsBackendConfig,
+}
+
+impl TtsVoice {
+  fn piper(name: &str, model: &str) -> TtsVoice {
+    TtsVoice {
+      name: name.to_owned(),
+      backend: TtsBackendConfig::Piper {
+        model: model.to_owned(),
+        player: "aplay".to_owned(),
+      },
+    }
+  }
+}
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct TtsConfig {
+  // Used when a viewer hasn't picked a voice, or picked one that no longer exists
+  pub default_voice: String,
+  pub voices: Vec<TtsVoice>,
+  pub filter: TtsFilterConfig,
+}
+
+impl Default for TtsConfig {
+  fn default() -> Self {
+    TtsConfig {
+      default_voice: "owl".to_owned(),
+      voices: vec![
+        TtsVoice {
+          name: "owl".to_owned(),
+          backend: TtsBackendConfig::Dsnote,
+        },
+        TtsVoice::piper("nervous", "en_GB-jenny_dioco-medium.onnx"),
+        TtsVoice::piper("clear", "en_US-amy-low.onnx"),
+        TtsVoice::piper("calm", "en_US-kathleen-low.onnx"),
+        TtsVoice::piper("polite", "en_US-hfc_female-medium.onnx"),
+        TtsVoice::piper("legit", "en_US-lessac-high.onnx"),
+      ],
+      filter: TtsFilterConfig::default(),
+    }
+  }
+}
+
+pub struct TtsVoices {
+  default_voice: String,
+  backends: HashMap<String, Arc<dyn TtsBackend + Send + Sync>>,
+}
+
+impl TtsVoices {
+  pub fn new(config: &TtsConfig) -> TtsVoices {
+    TtsVoices {
+      default_voice: config.default_voice.to_lowercase(),
+      backends: config
+        .voices
+        .iter()
+        .map(|voice| (voice.name.to_lowercase(), voice.backend.backend()))
+        .collect(),
+    }
+  }
+
+  pub fn contains(&self, voice: &str) -> bool {
+    self.backends.contains_key(&voice.to_lowercase())
+  }
+
+  pub fn names(&self) -> Vec<&str> {
+    let mut names = self
+      .backends
+      .keys()
+      .map(|name| name.as_str())
+      .collect::<Vec<_>>();
+    names.sort();
+    names
+  }
+
+  pub fn backend(&self, voice: Option<&str>) -> Option<Arc<dyn TtsBackend + Send + Sync>> {
+    voice
+      .and_then(|voice| self.backends.get(&voice.to_lowercase()))
+      .or_else(|| self.backends.get(&self.default_voice))
+      .cloned()
+  }
+}
+
+// Redeem input can start with "voice:<name>" to override the viewer's voice
+pub fn split_voice_prefix(input: &str) -> (Option<&str>, &str) {
+  let input = input.trim_start();
+  match input.split_once(char::is_whitespace) {
+    Some((first, rest)) if first.to_lowercase().starts_with("voice:") => {
+      (Some(&first["voice:".len()..]), rest.trim_start())
+    }
+    None if input.to_lowercase().starts_with("voice:") => (Some(&input["voice:".len()..]), ""),
+    _ => (None, input),
+  }
+}
@@ -0,0 +1,156 @@
+use std::{collections::HashSet, fmt};
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+use twitcheventsub::{error, TwitchEventSubApi};
+
+use crate::modules::{
+  filters::{looks_like_link, ChatFilters},
+  helix,
+};
+
+#[derive(Serialise, Deserialise, Clone)]
+#[serde(default)]
+pub struct TtsFilterConfig {
+  // Longer messages are cut off, counted in characters
+  pub max_length: usize,
+  // "noooooo" is read as "nooo"
+  pub max_repeated_characters: usize,
+  pub remove_emotes: bool,
+  // Emotes twitch doesn't know about, e.g. from BTTV or 7TV
+  pub emote_codes: Vec<String>,
+  // A viewer's first TTS waits for a moderator, later ones are read straight away
+  pub approve_first_redeems: bool,
+}
+
+impl Default for TtsFilterConfig {
+  fn default() -> Self {
+    TtsFilterConfig {
+      max_length: 300,
+      max_repeated_characters: 3,
+      remove_emotes: true,
+      emote_codes: Vec::new(),
+      approve_first_redeems: false,
+    }
+  }
+}
+
+// The blocked term itself never ends up in Display, that text is posted
+// back to chat when a redeem is refunded
+#[derive(Debug)]
+pub enum TtsRejection {
+  BlockedTerm(String),
+  Empty,
+}
+
+impl fmt::Display for TtsRejection {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      TtsRejection::BlockedTerm(_) => write!(f, "it contains a blocked term"),
+      TtsRejection::Empty => write!(f, "there was nothing left to read"),
+    }
+  }
+}
+
+// Redeem input is plain text, so emotes are recognised by name against
+// the channel's and twitch's own emote lists
+#[derive(Default)]
+pub struct EmoteNames {
+  emotes: HashSet<String>,
+  // Lowercase, cheermotes are the prefix followed by the bits (cheer100)
+  cheermote_prefixes: Vec<String>,
+}
+
+impl EmoteNames {
+  pub fn fetch(twitch: &mut TwitchEventSubApi, config: &TtsFilterConfig) -> EmoteNames {
+    let mut emotes = config.emote_codes.iter().cloned().collect::<HashSet<_>>();
+
+    match twitch.get_global_emotes() {
+      Ok(global) => emotes.extend(global.data.into_iter().map(|emote| emote.name)),
+      Err(e) => error!("Failed to fetch global emotes: {:?}", e),
+    }
+    let broadcaster_id = twitch.get_twitch_keys().broadcaster_account_id;
+    match twitch.get_channel_emotes(broadcaster_id) {
+      Ok(channel) => emotes.extend(channel.data.into_iter().map(|emote| emote.name)),
+      Err(e) => error!("Failed to fetch channel emotes: {:?}", e),
+    }
+
+    let cheermote_prefixes = match helix::get_cheermote_prefixes(twitch) {
+      Ok(prefixes) => prefixes
+        .into_iter()
+        .map(|prefix| prefix.to_lowercase())
+        .collect(),
+      Err(e) => {
+        error!("Failed to fetch cheermotes: {:?}", e);
+        Vec::new()
+      }
+    };
+
+    EmoteNames {
+      emotes,
+      cheermote_prefixes,
+    }
+  }
+
+  fn contains(&self, word: &str) -> bool {
+    if self.emotes.contains(word) {
+      return true;
+    }
+
+    let word = word.to_lowercase();
+    self.cheermote_prefixes.iter().any(|prefix| {
+      word
+        .strip_prefix(prefix.as_str())
+        .is_some_and(|bits| !bits.is_empty() && bits.chars().all(|c| c.is_ascii_digit()))
+    })
+  }
+}
+
+fn collapse_repeats(word: &str, max_repeats: usize) -> String {
+  let mut collapsed = String::new();
+  let mut last = None;
+  let mut repeats = 0;
+  for c in word.chars() {
+    if Some(c) == last {
+      repeats += 1;
+    } else {
+      last = Some(c);
+      repeats = 1;
+    }
+    if repeats <= max_repeats.max(1) {
+      collapsed.push(c);
+    }
+  }
+
+  collapsed
+}
+
+pub fn sanitise(
+  text: &str,
+  filters: &ChatFilters,
+  emotes: &EmoteNames,
+  config: &TtsFilterConfig,
+) -> Result<String, TtsRejection> {
+  let sanitised = text
+    .split_whitespace()
+    .filter(|word| !looks_like_link(&word.to_lowercase()))
+    .filter(|word| !(config.remove_emotes && emotes.contains(word)))
+    .map(|word| collapse_repeats(word, config.max_repeated_characters))
+    .collect::<Vec<_>>()
+    .join(" ")
+    .chars()
+    .take(config.max_length)
+    .collect::<String>();
+
+  // Collapsing can turn a disguised term back into the real one
+  for checked in [text, &sanitised] {
+    if let Some(term) = filters.blocked_terms_in(&checked.to_lowercase()).first() {
+      return Err(TtsRejection::BlockedTerm(term.to_string()));
+    }
+  }
+
+  if sanitised.trim().is_empty() {
+    return Err(TtsRejection::Empty);
+  }
+
+  Ok(sanitised)
+}
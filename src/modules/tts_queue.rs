@@ -0,0 +1,359 @@
+use std::collections::VecDeque;
+use std::process::Child;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use twitcheventsub::{error, TwitchEventSubApi};
+
+use crate::modules::{
+  redemptions::{PointsRedemption, RedeemOutcome},
+  tts::{Playback, TtsBackend, TtsError, TtsVoices},
+  viewers::Viewers,
+};
+
+// Higher priorities jump ahead of everything queued below them
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TtsPriority {
+  Redeem,
+  Support,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TtsControl {
+  Skip,
+  Pause,
+  Resume,
+  Clear,
+  Show,
+  Approve(u32),
+  Reject(u32),
+}
+
+impl TtsControl {
+  pub fn parse(words: &[&str]) -> Option<TtsControl> {
+    match words {
+      ["skip"] => Some(TtsControl::Skip),
+      ["pause"] => Some(TtsControl::Pause),
+      ["resume"] => Some(TtsControl::Resume),
+      ["clear"] => Some(TtsControl::Clear),
+      ["queue"] => Some(TtsControl::Show),
+      ["approve", id] => Some(TtsControl::Approve(id.parse().ok()?)),
+      ["reject", id] => Some(TtsControl::Reject(id.parse().ok()?)),
+      _ => None,
+    }
+  }
+}
+
+pub struct TtsRequest {
+  pub username: String,
+  // Viewers are keyed by login, the username is only for display
+  pub login: String,
+  pub voice: Option<String>,
+  pub text: String,
+  pub priority: TtsPriority,
+  // Redeems are fulfilled once they have been read out, and refunded if
+  // they are rejected, cleared, skipped or fail to play
+  pub redemption: Option<PointsRedemption>,
+}
+
+impl TtsRequest {
+  fn resolve(&self, twitch: &mut TwitchEventSubApi, outcome: RedeemOutcome) {
+    if let Some(redemption) = &self.redemption {
+      redemption.resolve(twitch, outcome);
+    }
+  }
+}
+
+enum Speaking {
+  Preparing(JoinHandle<Result<Playback, TtsError>>),
+  Playing(Child),
+  // Detached playback can't be waited on, so it gets an estimate
+  Detached(Instant, Duration),
+}
+
+struct Current {
+  text: String,
+  backend: Arc<dyn TtsBackend + Send + Sync>,
+  speaking: Speaking,
+  skipped: bool,
+  redemption: Option<PointsRedemption>,
+}
+
+fn report(backend: &Arc<dyn TtsBackend + Send + Sync>, e: impl std::fmt::Display) {
+  println!("TTS ({}) failed: {}", backend.name(), e);
+  error!("TTS ({}) failed: {}", backend.name(), e);
+}
+
+fn estimated_duration(text: &str) -> Duration {
+  Duration::from_secs((text.len() / 100 * 7).clamp(5, 18) as u64)
+}
+
+#[derive(Default)]
+pub struct TtsQueue {
+  requests: VecDeque<TtsRequest>,
+  current: Option<Current>,
+  paused: bool,
+  // Waiting for a moderator to approve them
+  held: Vec<(u32, TtsRequest)>,
+  next_held_id: u32,
+}
+
+impl TtsQueue {
+  pub fn push(&mut self, request: TtsRequest) {
+    let idx = self
+      .requests
+      .iter()
+      .position(|queued| queued.priority < request.priority)
+      .unwrap_or(self.requests.len());
+    self.requests.insert(idx, request);
+  }
+
+  pub fn hold(&mut self, request: TtsRequest) -> u32 {
+    self.next_held_id += 1;
+    let id = self.next_held_id;
+    println!(
+      "Holding TTS #{} from {} for approval: {}",
+      id, request.username, request.text
+    );
+    println!("    y {} approve | n {} reject", id, id);
+    self.held.push((id, request));
+    id
+  }
+
+  fn take_held(&mut self, id: u32) -> Option<TtsRequest> {
+    let idx = self.held.iter().position(|(held_id, _)| *held_id == id)?;
+    Some(self.held.remove(idx).1)
+  }
+
+  // The message being spoken is allowed to finish
+  pub fn pause(&mut self) -> bool {
+    let was_paused = self.paused;
+    self.paused = true;
+    !was_paused
+  }
+
+  pub fn resume(&mut self) -> bool {
+    let was_paused = self.paused;
+    self.paused = false;
+    was_paused
+  }
+
+  pub fn clear(&mut self, twitch: &mut TwitchEventSubApi) -> usize {
+    let cleared = self.requests.len();
+    for request in self.requests.drain(..) {
+      request.resolve(
+        twitch,
+        RedeemOutcome::Failed("your TTS was cleared from the queue".to_owned()),
+      );
+    }
+    cleared
+  }
+
+  pub fn skip(&mut self) -> bool {
+    let Some(current) = &mut self.current else {
+      return false;
+    };
+
+    current.skipped = true;
+    match &mut current.speaking {
+      Speaking::Playing(child) => {
+        let _ = child.kill();
+      }
+      Speaking::Detached(..) => {
+        if let Err(e) = current.backend.stop() {
+          report(&current.backend, e);
+        }
+      }
+      // Stopped as soon as it starts playing
+      Speaking::Preparing(_) => {}
+    }
+    true
+  }
+
+  // Used by both the !tts command and the terminal keys
+  pub fn control(
+    &mut self,
+    twitch: &mut TwitchEventSubApi,
+    viewers: &mut Viewers,
+    control: TtsControl,
+  ) -> String {
+    match control {
+      TtsControl::Skip if self.skip() => "Skipped the current TTS message.".to_owned(),
+      TtsControl::Skip => "Nothing is being spoken.".to_owned(),
+      TtsControl::Pause if self.pause() => "TTS is paused.".to_owned(),
+      TtsControl::Pause => "TTS is already paused.".to_owned(),
+      TtsControl::Resume if self.resume() => "TTS is back on.".to_owned(),
+      TtsControl::Resume => "TTS isn't paused.".to_owned(),
+      TtsControl::Clear => format!("Cleared {} TTS messages.", self.clear(twitch)),
+      TtsControl::Approve(id) => match self.take_held(id) {
+        Some(request) => {
+          // Only the first redeem needs approving
+          viewers.get_mut(&request.login).tts_approved = true;
+          viewers.save();
+          let approved_text = format!("Approved {}'s TTS.", request.username);
+          self.push(request);
+          approved_text
+        }
+        None => format!("There is no held TTS #{}.", id),
+      },
+      TtsControl::Reject(id) => match self.take_held(id) {
+        Some(request) => {
+          request.resolve(
+            twitch,
+            RedeemOutcome::Failed("a moderator rejected your TTS".to_owned()),
+          );
+          format!("Rejected {}'s TTS.", request.username)
+        }
+        None => format!("There is no held TTS #{}.", id),
+      },
+      TtsControl::Show => {
+        self.print();
+        format!(
+          "{} TTS messages queued{}.",
+          self.requests.len(),
+          if self.paused { ", TTS is paused" } else { "" }
+        )
+      }
+    }
+  }
+
+  pub fn print(&self) {
+    println!(
+      "TTS{}: {} queued",
+      if self.paused { " (paused)" } else { "" },
+      self.requests.len()
+    );
+    if let Some(current) = &self.current {
+      println!("  Speaking: {}", current.text);
+    }
+    for (i, request) in self.requests.iter().enumerate() {
+      println!("  {}. [{:?}] {}", i + 1, request.priority, request.text);
+    }
+    for (id, request) in &self.held {
+      println!("  Held #{} from {}: {}", id, request.username, request.text);
+    }
+  }
+
+  pub fn tick(&mut self, twitch: &mut TwitchEventSubApi, voices: &TtsVoices) {
+    if let Some(mut current) = self.current.take() {
+      let redemption = current.redemption.take();
+      match TtsQueue::advance(current) {
+        Ok(mut current) => {
+          current.redemption = redemption;
+          self.current = Some(current);
+        }
+        Err(outcome) => {
+          if let Some(redemption) = redemption {
+            redemption.resolve(twitch, outcome);
+          }
+        }
+      }
+    }
+
+    if self.current.is_some() || self.paused {
+      return;
+    }
+
+    let Some(request) = self.requests.pop_front() else {
+      return;
+    };
+    let Some(backend) = voices.backend(request.voice.as_deref()) else {
+      request.resolve(
+        twitch,
+        RedeemOutcome::Failed("there is no TTS voice to read it".to_owned()),
+      );
+      return;
+    };
+
+    let speaker = backend.clone();
+    let text = request.text.clone();
+    self.current = Some(Current {
+      text: request.text,
+      backend,
+      speaking: Speaking::Preparing(thread::spawn(move || speaker.speak(&text))),
+      skipped: false,
+      redemption: request.redemption,
+    });
+  }
+
+  // Returns the current message if it is still being spoken, otherwise
+  // how its redeem turned out
+  fn advance(current: Current) -> Result<Current, RedeemOutcome> {
+    let Current {
+      text,
+      backend,
+      speaking,
+      skipped,
+      redemption,
+    } = current;
+
+    let finished = || {
+      if skipped {
+        RedeemOutcome::Failed("your TTS was skipped".to_owned())
+      } else {
+        RedeemOutcome::Succeeded
+      }
+    };
+    let failed = || RedeemOutcome::Failed("your TTS couldn't be played".to_owned());
+
+    let speaking = match speaking {
+      Speaking::Preparing(handle) if !handle.is_finished() => Speaking::Preparing(handle),
+      Speaking::Preparing(handle) => match handle.join() {
+        Ok(Ok(Playback::Process(mut child))) => {
+          if skipped {
+            let _ = child.kill();
+            return Err(finished());
+          }
+          Speaking::Playing(child)
+        }
+        Ok(Ok(Playback::Detached)) => {
+          if skipped {
+            if let Err(e) = backend.stop() {
+              report(&backend, e);
+            }
+            return Err(finished());
+          }
+          Speaking::Detached(Instant::now(), estimated_duration(&text))
+        }
+        Ok(Ok(Playback::Finished)) => return Err(finished()),
+        Ok(Err(e)) => {
+          report(&backend, e);
+          return Err(failed());
+        }
+        Err(_) => {
+          report(&backend, "speech thread panicked");
+          return Err(failed());
+        }
+      },
+      Speaking::Playing(mut child) => match child.try_wait() {
+        Ok(None) => Speaking::Playing(child),
+        Ok(Some(status)) => {
+          if !status.success() && !skipped {
+            report(&backend, status);
+            return Err(failed());
+          }
+          return Err(finished());
+        }
+        Err(e) => {
+          report(&backend, e);
+          return Err(failed());
+        }
+      },
+      Speaking::Detached(started, estimate) => {
+        if started.elapsed() >= estimate {
+          return Err(finished());
+        }
+        Speaking::Detached(started, estimate)
+      }
+    };
+
+    Ok(Current {
+      text,
+      backend,
+      speaking,
+      skipped,
+      redemption,
+    })
+  }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+
+use serde::{Deserialize as Deserialise, Serialize as Serialise};
+
+use crate::modules::consts::VIEWERS_FILE;
+
+#[derive(Serialise, Deserialise, Default, Clone)]
+#[serde(default)]
+pub struct Viewer {
+  pub voice: Option<String>,
+  // Set once a moderator has approved one of their TTS redeems
+  pub tts_approved: bool,
+  // Unix timestamp of when they followed
+  pub followed_at: Option<u64>,
+}
+
+// Per viewer preferences, keyed by lowercase login
+#[derive(Serialise, Deserialise, Default)]
+pub struct Viewers {
+  viewers: HashMap<String, Viewer>,
+}
+
+impl Viewers {
+  pub fn load() -> Viewers {
+    let mut viewers = Viewers::default();
+    if let Ok(viewers_string) = fs::read_to_string(VIEWERS_FILE) {
+      viewers = serde_json::from_str(&viewers_string).unwrap();
+    }
+
+    viewers
+  }
+
+  pub fn save(&self) {
+    let mut file = fs::File::create(VIEWERS_FILE).unwrap();
+    if let Ok(viewers) = serde_json::to_string(self) {
+      file.write_all(viewers.as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+  }
+
+  pub fn get(&self, username: &str) -> Option<&Viewer> {
+    self.viewers.get(&username.to_lowercase())
+  }
+
+  pub fn get_mut(&mut self, username: &str) -> &mut Viewer {
+    self.viewers.entry(username.to_lowercase()).or_default()
+  }
+
+  pub fn voice(&self, username: &str) -> Option<&str> {
+    self.get(username)?.voice.as_deref()
+  }
+}